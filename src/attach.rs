@@ -0,0 +1,113 @@
+use std::{ffi::OsStr, time::{Duration, Instant}};
+
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+use crate::{engine::is_halo_memory, memory::{Memory, ProcessSource, XBOX_MEMORY_SIZE}};
+
+// xemu ships as xemu.exe on Windows and as a plain xemu binary on Linux.
+const XEMU_PROCESS_NAMES: [&str; 2] = ["xemu.exe", "xemu"];
+
+// How often we search xemu for the guest RAM while the game is still booting.
+pub const ATTACH_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+// Rate limits attach attempts, returns true and records the attempt once ATTACH_RETRY_INTERVAL has passed.
+pub fn attempt_due(last_attempt: &mut Option<Instant>) -> bool {
+    let due = last_attempt.is_none_or(|last_attempt| last_attempt.elapsed() >= ATTACH_RETRY_INTERVAL);
+    if due {
+        *last_attempt = Some(Instant::now());
+    }
+
+    due
+}
+
+pub struct XemuProcess {
+    pub pid: u32,
+    pub command_line: String,
+    // Seconds since the unix epoch.
+    pub start_time: u64,
+    pub window_title: Option<String>
+}
+
+pub fn find_xemu_processes() -> Vec<XemuProcess> {
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(ProcessesToUpdate::All, ProcessRefreshKind::new().with_cmd(UpdateKind::OnlyIfNotSet));
+
+    let mut processes: Vec<_> = XEMU_PROCESS_NAMES.iter()
+        .flat_map(|name| sys.processes_by_exact_name(OsStr::new(name)))
+        .map(|process| XemuProcess {
+            pid: process.pid().as_u32(),
+            command_line: process.cmd().iter().map(|arg| arg.to_string_lossy()).collect::<Vec<_>>().join(" "),
+            start_time: process.start_time(),
+            window_title: window_title(process.pid().as_u32())
+        })
+        .collect();
+
+    processes.sort_by_key(|process| process.start_time);
+    processes
+}
+
+pub fn find_xemu_pids() -> Vec<u32> {
+    find_xemu_processes().iter().map(|process| process.pid).collect()
+}
+
+#[cfg(windows)]
+fn window_title(pid: u32) -> Option<String> {
+    use windows::Win32::{
+        Foundation::{BOOL, HWND, LPARAM},
+        UI::WindowsAndMessaging::{EnumWindows, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible}
+    };
+
+    struct WindowSearch {
+        pid: u32,
+        title: Option<String>
+    }
+
+    unsafe extern "system" fn visit_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let search = &mut *(lparam.0 as *mut WindowSearch);
+
+        let mut window_pid = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut window_pid));
+        if window_pid != search.pid || !IsWindowVisible(hwnd).as_bool() {
+            return BOOL(1);
+        }
+
+        let mut title = [0u16; 256];
+        let length = GetWindowTextW(hwnd, &mut title);
+        if length <= 0 {
+            return BOOL(1);
+        }
+
+        search.title = Some(String::from_utf16_lossy(&title[..length as usize]));
+        BOOL(0)
+    }
+
+    let mut search = WindowSearch { pid, title: None };
+    unsafe {
+        // Stopping the enumeration early is reported as an error, which we do not care about.
+        let _ = EnumWindows(Some(visit_window), LPARAM(&mut search as *mut WindowSearch as isize));
+    }
+
+    search.title
+}
+
+// Window titles would need a connection to the display server, which we do not have outside of Windows.
+#[cfg(not(windows))]
+fn window_title(_pid: u32) -> Option<String> {
+    None
+}
+
+// Walk the memory map of xemu for a 64 MiB mapping that holds Halo, returning its base and an attached Memory.
+pub fn find_guest_memory(pid: u32) -> Option<(usize, Memory)> {
+    for virtual_address in ProcessSource::find_regions(pid, XBOX_MEMORY_SIZE) {
+        // Listing regions needs fewer rights than reading and writing them, an elevated xemu can only be queried.
+        let Ok(source) = ProcessSource::new(virtual_address, XBOX_MEMORY_SIZE, pid) else { continue; };
+        let mut memory = Memory::new(source);
+        memory.refresh();
+
+        if is_halo_memory(&memory) {
+            return Some((virtual_address, memory));
+        }
+    }
+
+    None
+}
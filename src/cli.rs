@@ -0,0 +1,402 @@
+use std::{io::{self, Write}, sync::Arc};
+
+use serde::Serialize;
+
+use crate::{
+    attach::{find_guest_memory, find_xemu_pids},
+    engine::{build_snapshot, object_type_string, Datum, EngineSnapshot, Object, ObjectHeaderEntry, PlayerDataEntry, PlayersGlobals},
+    export::{write_object_rows, OBJECTS_CSV_HEADER},
+    memory::{DumpSource, Memory},
+    sampler::{SampleRate, Sampler, SamplerEvent},
+    server::{Server, DEFAULT_PORT}
+};
+
+const USAGE: &str = "\
+Usage: objectviewer [COMMAND] [OPTIONS]
+
+Without a command the viewer window is opened.
+
+Commands:
+  dump               Print the object table
+  players            Print the players globals and local players
+  watch --slot N     Print object slot N on every game tick until xemu exits
+  serve              Publish every game tick to WebSocket clients on localhost until xemu exits
+  help               Print this message
+
+Options:
+  --pid PID          Attach to this xemu instead of the only running one
+  --dump PATH        Read a RAM dump instead of attaching (not with watch)
+  --json             Print JSON instead of a table, one object per line for watch
+  --csv              Print the object table as CSV (dump only)
+  --include-free     Also print free slots (dump only)
+  --port PORT        Port to serve on, 7878 by default (serve only)";
+
+#[derive(PartialEq, Clone, Copy)]
+enum Command {
+    Dump,
+    Players,
+    Watch,
+    Serve,
+    Help
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum OutputFormat {
+    Table,
+    Json,
+    Csv
+}
+
+struct Options {
+    command: Command,
+    pid: Option<u32>,
+    dump: Option<String>,
+    slot: Option<usize>,
+    port: Option<u16>,
+    format: OutputFormat,
+    include_free: bool
+}
+
+// Errors are printed to stderr by run, usage errors along with the usage.
+enum CliError {
+    Usage(String),
+    Failed(String),
+    // Whatever reads stdout went away, e.g. `head`, so there is no one left to tell.
+    Closed
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> CliError {
+        if e.kind() == io::ErrorKind::BrokenPipe {
+            return CliError::Closed;
+        }
+        CliError::Failed(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for CliError {
+    fn from(e: serde_json::Error) -> CliError {
+        match e.io_error_kind() {
+            Some(io::ErrorKind::BrokenPipe) => CliError::Closed,
+            _ => CliError::Failed(e.to_string())
+        }
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, CliError> {
+    let value = value.ok_or_else(|| CliError::Usage(format!("{} needs a value", flag)))?;
+    value.parse().map_err(|_| CliError::Usage(format!("Invalid value for {}: {}", flag, value)))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, CliError> {
+    let command = match args[0].as_str() {
+        "dump" => Command::Dump,
+        "players" => Command::Players,
+        "watch" => Command::Watch,
+        "serve" => Command::Serve,
+        "help" | "--help" | "-h" => Command::Help,
+        command => return Err(CliError::Usage(format!("Unknown command: {}", command)))
+    };
+
+    let mut options = Options {
+        command,
+        pid: None,
+        dump: None,
+        slot: None,
+        port: None,
+        format: OutputFormat::Table,
+        include_free: false
+    };
+
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pid" => options.pid = Some(parse_value(arg, args.next())?),
+            "--dump" => options.dump = Some(parse_value(arg, args.next())?),
+            "--slot" => options.slot = Some(parse_value(arg, args.next())?),
+            "--port" => options.port = Some(parse_value(arg, args.next())?),
+            "--json" => options.format = OutputFormat::Json,
+            "--csv" => options.format = OutputFormat::Csv,
+            "--include-free" => options.include_free = true,
+            arg => return Err(CliError::Usage(format!("Unknown option: {}", arg)))
+        }
+    }
+
+    if options.command == Command::Watch && options.slot.is_none() {
+        return Err(CliError::Usage("watch needs --slot".to_string()));
+    }
+
+    // A dump never changes, watch would wait for a second tick forever.
+    if options.command == Command::Watch && options.dump.is_some() {
+        return Err(CliError::Usage("watch needs a running xemu, not --dump".to_string()));
+    }
+
+    if options.format == OutputFormat::Csv && options.command != Command::Dump {
+        return Err(CliError::Usage("--csv only works with dump".to_string()));
+    }
+
+    Ok(options)
+}
+
+// The memory to read along with a name for it.
+fn open_memory(options: &Options) -> Result<(String, Memory), CliError> {
+    if let Some(path) = &options.dump {
+        return DumpSource::open(path)
+            .map(|dump| (path.clone(), Memory::new(dump)))
+            .map_err(|e| CliError::Failed(format!("Could not open dump {}: {}", path, e)));
+    }
+
+    let pid = match (options.pid, &find_xemu_pids()[..]) {
+        (Some(pid), _) => pid,
+        (None, [pid]) => *pid,
+        (None, []) => return Err(CliError::Failed("Could not find running instance of xemu".to_string())),
+        (None, pids) => return Err(CliError::Failed(format!("Found {} instances of xemu, pick one with --pid", pids.len())))
+    };
+
+    find_guest_memory(pid)
+        .map(|(_, memory)| (format!("xemu {}", pid), memory))
+        .ok_or_else(|| CliError::Failed(format!("Could not find the Halo guest memory in xemu ({})", pid)))
+}
+
+fn snapshot(options: &Options) -> Result<EngineSnapshot, CliError> {
+    let (_, mut memory) = open_memory(options)?;
+    memory.refresh();
+
+    build_snapshot(&memory).map_err(|e| CliError::Failed(format!("Nothing to show: {}", e)))
+}
+
+fn format_position(position: &[f32; 3]) -> String {
+    format!("{:>10.4} {:>10.4} {:>10.4}", position[0], position[1], position[2])
+}
+
+fn tag_name<'a>(snapshot: &'a EngineSnapshot, object: &Object) -> &'a str {
+    snapshot.tags.get(&object.tag_index).map(String::as_str).unwrap_or("UNKNOWN")
+}
+
+fn print_dump(out: &mut impl Write, options: &Options, snapshot: &EngineSnapshot) -> Result<(), CliError> {
+    match options.format {
+        OutputFormat::Json => {
+            serde_json::to_writer(&mut *out, snapshot)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            writeln!(out, "{}", OBJECTS_CSV_HEADER)?;
+            write_object_rows(out, snapshot, options.include_free)?;
+        }
+        OutputFormat::Table => {
+            let header = &snapshot.object_header;
+            writeln!(
+                out,
+                "Map Name: {} Tick: {} | Capacity: {} Size: {} Next Index: {} ({}) Next ID: {}",
+                snapshot.map_name,
+                snapshot.game_time_globals.local_time,
+                header.capacity,
+                header.size,
+                header.next_index,
+                snapshot.first_free_index(),
+                header.next_id
+            )?;
+            writeln!(out, "{:>10} {:>5} {:>5} {:>6} {:>10} {:>10} {:>10} {:<7} Tag", "Datum", "Index", "ID", "Player", "X", "Y", "Z", "Type")?;
+
+            for index in (0..header.max_entries as usize).rev() {
+                let (Some(object_pool_entry), Some(game_object_entry)) = (&snapshot.object_header_entries[index], &snapshot.object_entries[index]) else {
+                    if options.include_free {
+                        writeln!(out, "{:>10} {:>5} {:>5}", "", index, "Free")?;
+                    }
+                    continue;
+                };
+
+                let datum_handle = Datum::from_parts(index as u16, object_pool_entry.id);
+                let player = if let Some(player_index) = snapshot.find_local_player_index_from_unit_index(index as u16) {
+                    player_index.to_string()
+                } else if let Some(local_dead_player_index) = snapshot.find_next_object_datum_player(datum_handle.clone()) {
+                    format!("next {}", local_dead_player_index)
+                } else {
+                    String::default()
+                };
+
+                writeln!(
+                    out,
+                    "{:>10} {:>5} {:>5} {:>6} {} {:<7} {}",
+                    datum_handle.get_handle(),
+                    index,
+                    object_pool_entry.id,
+                    player,
+                    format_position(&game_object_entry.position),
+                    object_type_string(object_pool_entry.data_type),
+                    tag_name(snapshot, game_object_entry)
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct PlayersOutput<'a> {
+    player_globals: &'a PlayersGlobals,
+    players: &'a [Option<PlayerDataEntry>]
+}
+
+fn print_players(out: &mut impl Write, options: &Options, snapshot: &EngineSnapshot) -> Result<(), CliError> {
+    let player_globals = &snapshot.player_globals;
+
+    if options.format == OutputFormat::Json {
+        serde_json::to_writer(&mut *out, &PlayersOutput { player_globals, players: &snapshot.player_entries })?;
+        writeln!(out)?;
+        return Ok(());
+    }
+
+    writeln!(out, "Respawn Failure: {}", player_globals.respawn_failure)?;
+    writeln!(out, "Are All Dead: {}", player_globals.are_all_dead)?;
+    writeln!(out, "Input Disabled: {}", player_globals.input_disabled)?;
+    writeln!(out, "Teleported: {}", player_globals.teleported)?;
+
+    let position = |datum: &Datum| {
+        snapshot.object_entries.get(datum.get_index() as usize)
+            .and_then(|entry| entry.as_ref())
+            .map(|object| format_position(&object.position))
+            .unwrap_or_else(|| "None".to_string())
+    };
+
+    for player in &player_globals.local_players {
+        if player.is_invalid() { continue; }
+
+        let player_index = player.get_index() as usize;
+        let local_dead_player = player_globals.local_dead_players.get(player_index);
+        let next_datum = local_dead_player.map(|datum| format!("{:?}", datum)).unwrap_or_else(|| "None".to_string());
+        writeln!(out, "-------------- Player {} --------------", player_index)?;
+
+        match snapshot.player_entries.get(player_index).and_then(|entry| entry.as_ref()) {
+            Some(entry) => {
+                writeln!(out, "Current Object Datum: {:?}", entry.slave_unit_index)?;
+                writeln!(out, "Next Object Datum: {}", next_datum)?;
+                writeln!(out, "Last Object Datum: {:?}", entry.last_slave_unit_index)?;
+                writeln!(out, "Position: {}", position(&entry.slave_unit_index))?;
+            }
+            None => {
+                writeln!(out, "Unit Handle: None")?;
+                writeln!(out, "Next Object Datum: {}", next_datum)?;
+            }
+        }
+
+        writeln!(out, "Next Datum Position: {}", local_dead_player.map(position).unwrap_or_else(|| "None".to_string()))?;
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct WatchLine<'a> {
+    tick: u32,
+    index: usize,
+    header: Option<&'a ObjectHeaderEntry>,
+    object: Option<&'a Object>
+}
+
+fn watch(out: &mut impl Write, options: &Options) -> Result<(), CliError> {
+    let slot = options.slot.unwrap_or_default();
+    let (_, memory) = open_memory(options)?;
+    let sampler = Sampler::spawn(memory, SampleRate::GameTick);
+
+    if options.format == OutputFormat::Table {
+        writeln!(out, "{:>10} {:>5} {:>10} {:>10} {:>10} {:<7} Tag", "Tick", "ID", "X", "Y", "Z", "Type")?;
+    }
+
+    while let Some(event) = sampler.wait_event() {
+        let snapshot = match event {
+            SamplerEvent::Sample { snapshot: Ok(snapshot), dropped_ticks, .. } => {
+                if dropped_ticks > 0 {
+                    eprintln!("Dropped {} ticks", dropped_ticks);
+                }
+                snapshot
+            }
+            SamplerEvent::Sample { snapshot: Err(e), .. } => {
+                eprintln!("Nothing to show: {}", e);
+                continue;
+            }
+            SamplerEvent::RecordingStopped { .. } => continue,
+            SamplerEvent::Exited => return Err(CliError::Failed("xemu exited".to_string()))
+        };
+
+        if slot >= snapshot.object_header.max_entries as usize {
+            return Err(CliError::Failed(format!("Slot {} is outside the object table of {} slots", slot, snapshot.object_header.max_entries)));
+        }
+
+        let tick = snapshot.game_time_globals.local_time;
+        let header = snapshot.object_header_entries[slot].as_ref();
+        let object = snapshot.object_entries[slot].as_ref();
+
+        match (options.format, header, object) {
+            (OutputFormat::Json, _, _) => {
+                serde_json::to_writer(&mut *out, &WatchLine { tick, index: slot, header, object })?;
+                writeln!(out)?;
+            }
+            (_, Some(header), Some(object)) => writeln!(
+                out,
+                "{:>10} {:>5} {} {:<7} {}",
+                tick,
+                header.id,
+                format_position(&object.position),
+                object_type_string(header.data_type),
+                tag_name(&snapshot, object)
+            )?,
+            _ => writeln!(out, "{:>10} {:>5}", tick, "Free")?
+        }
+
+        out.flush()?;
+    }
+
+    Ok(())
+}
+
+fn serve(options: &Options) -> Result<(), CliError> {
+    let (name, memory) = open_memory(options)?;
+    let port = options.port.unwrap_or(DEFAULT_PORT);
+    let server = Server::start(port).map_err(|e| CliError::Failed(format!("Could not serve on port {}: {}", port, e)))?;
+    let sampler = Sampler::spawn(memory, SampleRate::GameTick);
+    eprintln!("Serving {} on ws://{}", name, server.address());
+
+    while let Some(event) = sampler.wait_event() {
+        match event {
+            SamplerEvent::Sample { snapshot: Ok(snapshot), .. } => server.publish(0, &name, &Arc::from(snapshot)),
+            SamplerEvent::Sample { snapshot: Err(e), .. } => eprintln!("Nothing to show: {}", e),
+            SamplerEvent::RecordingStopped { .. } => {}
+            SamplerEvent::Exited => return Err(CliError::Failed("xemu exited".to_string()))
+        }
+
+        for error in server.take_errors() {
+            eprintln!("{}", error);
+        }
+    }
+
+    Ok(())
+}
+
+// Run the command in `args` (without the program name) and return the exit code.
+pub fn run(args: &[String]) -> i32 {
+    let result = parse_options(args).and_then(|options| {
+        let mut out = io::stdout().lock();
+
+        match options.command {
+            Command::Help => writeln!(out, "{}", USAGE).map_err(CliError::from),
+            Command::Dump => print_dump(&mut out, &options, &snapshot(&options)?),
+            Command::Players => print_players(&mut out, &options, &snapshot(&options)?),
+            Command::Watch => watch(&mut out, &options),
+            Command::Serve => serve(&options)
+        }
+    });
+
+    match result {
+        Ok(()) | Err(CliError::Closed) => 0,
+        Err(CliError::Usage(message)) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            2
+        }
+        Err(CliError::Failed(message)) => {
+            eprintln!("{}", message);
+            1
+        }
+    }
+}
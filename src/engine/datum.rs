@@ -1,18 +1,20 @@
 use std::fmt;
 
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
 const INVALID_HANDLE: u32 = 4294967295;
 
 #[repr(C)]
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Datum(u32);
 
 impl Datum {
     pub fn from_parts(index: u16, id: u16) -> Datum {
-        Datum { 0: ((id as u32) << 16) | index as u32 } 
+        Datum(((id as u32) << 16) | index as u32)
     }
 
     pub fn from_raw(handle: u32) -> Datum {
-        Datum { 0: handle } 
+        Datum(handle)
     }
 
     pub fn get_index(&self) -> u16 {
@@ -42,4 +44,14 @@ impl fmt::Debug for Datum {
          .field("ID", &self.get_id())
          .finish()
     }
-}
\ No newline at end of file
+}
+
+impl Serialize for Datum {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut datum = serializer.serialize_struct("Datum", 3)?;
+        datum.serialize_field("index", &self.get_index())?;
+        datum.serialize_field("id", &self.get_id())?;
+        datum.serialize_field("handle", &self.get_handle())?;
+        datum.end()
+    }
+}
@@ -0,0 +1,125 @@
+use super::{Datum, EngineSnapshot, Object, PlayersGlobals};
+
+// Names of the players globals changes that are matched on elsewhere.
+pub const LOCAL_DEAD_PLAYERS: &str = "Local Dead Players";
+pub const RESPAWN_FAILURE: &str = "Respawn Failure";
+
+// Positions closer than this are considered unchanged, to keep float noise out of the diff.
+const POSITION_EPSILON: f32 = 0.0001;
+
+#[derive(Debug, Clone)]
+pub struct FieldChange {
+    pub name: &'static str,
+    pub before: String,
+    pub after: String
+}
+
+#[derive(Debug, Clone)]
+pub enum SlotChange {
+    Freed { id: u16, tag: String },
+    Filled { id: u16, tag: String },
+    IdChanged { before: u16, after: u16 },
+    TagChanged { before: String, after: String },
+    Moved { before: [f32; 3], after: [f32; 3] }
+}
+
+#[derive(Debug, Clone)]
+pub struct SlotDiff {
+    pub index: usize,
+    pub change: SlotChange
+}
+
+// What changed between two snapshots of the same map, `before` is usually the pinned one.
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotDiff {
+    pub object_header: Vec<FieldChange>,
+    pub player_globals: Vec<FieldChange>,
+    pub slots: Vec<SlotDiff>
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.object_header.is_empty() && self.player_globals.is_empty() && self.slots.is_empty()
+    }
+}
+
+fn tag_name(snapshot: &EngineSnapshot, object: &Object) -> String {
+    snapshot.tags.get(&object.tag_index)
+        .and_then(|tag| tag.rsplit('\\').next())
+        .unwrap_or("UNKNOWN")
+        .to_string()
+}
+
+fn datums(datums: &[Datum]) -> String {
+    datums.iter()
+        .map(|datum| if datum.is_invalid() { "-".to_string() } else { format!("{}", datum.get_index()) })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn compare<T: PartialEq + ToString>(changes: &mut Vec<FieldChange>, name: &'static str, before: T, after: T) {
+    if before != after {
+        changes.push(FieldChange { name, before: before.to_string(), after: after.to_string() });
+    }
+}
+
+pub fn diff_player_globals(players_before: &PlayersGlobals, players_after: &PlayersGlobals) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    compare(&mut changes, "Local Players", datums(&players_before.local_players), datums(&players_after.local_players));
+    compare(&mut changes, LOCAL_DEAD_PLAYERS, datums(&players_before.local_dead_players), datums(&players_after.local_dead_players));
+    compare(&mut changes, "Local Player Count", players_before.local_player_count, players_after.local_player_count);
+    compare(&mut changes, "Double Speed Ticks", players_before.double_speed_ticks_remaining, players_after.double_speed_ticks_remaining);
+    compare(&mut changes, "Are All Dead", players_before.are_all_dead, players_after.are_all_dead);
+    compare(&mut changes, "Input Disabled", players_before.input_disabled, players_after.input_disabled);
+    compare(&mut changes, RESPAWN_FAILURE, players_before.respawn_failure, players_after.respawn_failure);
+    compare(&mut changes, "Teleported", players_before.teleported, players_after.teleported);
+    changes
+}
+
+pub fn diff_snapshots(before: &EngineSnapshot, after: &EngineSnapshot) -> SnapshotDiff {
+    let mut diff = SnapshotDiff::default();
+
+    let (header_before, header_after) = (&before.object_header, &after.object_header);
+    compare(&mut diff.object_header, "Capacity", header_before.capacity, header_after.capacity);
+    compare(&mut diff.object_header, "Size", header_before.size, header_after.size);
+    compare(&mut diff.object_header, "Next Index", header_before.next_index, header_after.next_index);
+    compare(&mut diff.object_header, "Next ID", header_before.next_id, header_after.next_id);
+
+    diff.player_globals = diff_player_globals(&before.player_globals, &after.player_globals);
+
+    let slot_count = before.object_entries.len().max(after.object_entries.len());
+    for index in 0..slot_count {
+        let change = |change| SlotDiff { index, change };
+
+        match (before.slot(index), after.slot(index)) {
+            (None, None) => {}
+            (Some((header_entry, object)), None) => {
+                diff.slots.push(change(SlotChange::Freed { id: header_entry.id, tag: tag_name(before, object) }));
+            }
+            (None, Some((header_entry, object))) => {
+                diff.slots.push(change(SlotChange::Filled { id: header_entry.id, tag: tag_name(after, object) }));
+            }
+            (Some((header_before, object_before)), Some((header_after, object_after))) => {
+                if header_before.id != header_after.id {
+                    diff.slots.push(change(SlotChange::IdChanged { before: header_before.id, after: header_after.id }));
+                }
+
+                if object_before.tag_index != object_after.tag_index {
+                    diff.slots.push(change(SlotChange::TagChanged {
+                        before: tag_name(before, object_before),
+                        after: tag_name(after, object_after)
+                    }));
+                }
+
+                let moved = object_before.position.iter().zip(&object_after.position)
+                    .any(|(axis_before, axis_after)| (axis_before - axis_after).abs() > POSITION_EPSILON);
+
+                if moved {
+                    diff.slots.push(change(SlotChange::Moved { before: object_before.position, after: object_after.position }));
+                }
+            }
+        }
+    }
+
+    diff
+}
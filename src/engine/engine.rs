@@ -1,8 +1,10 @@
-use std::{collections::HashMap, ffi::CStr};
+use std::{collections::{hash_map::Entry, HashMap}, ffi::CStr, mem::offset_of};
 
-use crate::memory::Memory;
+use serde::Serialize;
 
-use super::{datum::Datum, EntityManager};
+use crate::memory::{Memory, MemoryError};
+
+use super::{datum::Datum, entry_address, serialize, EntityManager, SnapshotError};
 
 // Halo 1 Xbox Retail
 const HALO_OBJECT_POOL_HEADER_ADDR: u32 = 0x000B9370;
@@ -21,13 +23,14 @@ const RNCS: u32 = 1935896178;
 // Halo Structs
 const MAXIMUM_NUMBER_OF_LOCAL_PLAYERS: usize = 4;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 #[repr(C)]
 pub struct GameOptions {
     pub unk_0: u32,
     pub unk_4: u16,
     pub difficulty: i16,
     pub random_seed: i32,
+    #[serde(serialize_with = "serialize::c_string")]
     pub map_name: [u8; 256]
 }
 
@@ -38,7 +41,7 @@ impl GameOptions {
 }
 
   
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 #[repr(C)]
 pub struct GameGlobals {
     pub map_loaded: u8,
@@ -49,7 +52,7 @@ pub struct GameGlobals {
     pub game_options: GameOptions
 }  
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 #[repr(C)]
 pub struct GameTimeGlobals {
     pub initalized: u8,
@@ -63,7 +66,7 @@ pub struct GameTimeGlobals {
     pub leftover_dt: f32
 }  
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 #[repr(C)]
 pub struct PlayersGlobals {
     pub unknown_1: i32,
@@ -77,23 +80,27 @@ pub struct PlayersGlobals {
     pub respawn_failure: u16,
     pub teleported: u8,
     pub unk_flags: u8,
+    #[serde(serialize_with = "serialize::bytes")]
     pub combined_pvs: [u8; 0x40],
+    #[serde(serialize_with = "serialize::bytes")]
     pub combined_pvs_local: [u8; 0x40]
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 #[repr(C)]
 pub struct PlayerDataEntry {
     pub id: u16,
     pub local_player_index: u16,
+    #[serde(serialize_with = "serialize::utf16_string")]
     pub player_name: [u16; 12],
     pub unknown_1: [i32; 6],
     pub slave_unit_index: Datum, // datum
     pub last_slave_unit_index: Datum, // datum
+    #[serde(serialize_with = "serialize::bytes")]
     pub unknown_2: [u8; 150]
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 #[repr(C)]
 pub struct TagEntry {
     pub tag_class: u32,
@@ -106,7 +113,7 @@ pub struct TagEntry {
     unknown_2: u32
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 #[repr(C)]
 pub struct TagHeader {
     tag_array_ptr: u32,
@@ -127,7 +134,7 @@ impl TagHeader {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 #[repr(C)]
 pub struct ObjectHeaderEntry {
     pub id: u16,
@@ -138,7 +145,7 @@ pub struct ObjectHeaderEntry {
     pub object_address: u32
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 #[repr(C)]
 pub struct ObjectListHeader {
     pub header_head: u32,
@@ -152,7 +159,7 @@ pub struct ObjectListHeader {
 const NUMBER_OF_OUTGOING_OBJECT_FUNCTIONS: usize = 4;
 const MAXIMUM_REGIONS_PER_OBJECT: usize = 8;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 #[repr(C)]
 pub struct Object {
     pub tag_index: u32,
@@ -214,6 +221,7 @@ pub struct Object {
     pub unk_294: u16,
     pub unk_296: [u8; MAXIMUM_REGIONS_PER_OBJECT],
     pub unk_304: [u8; MAXIMUM_REGIONS_PER_OBJECT],
+    #[serde(serialize_with = "serialize::bytes")]
     pub unk_312: [u8; 0x60],
     pub unk_408: u32,
     pub unk_412: u32,
@@ -237,8 +245,18 @@ pub fn object_type_string(data_type: u8) -> &'static str {
     }
 }
 
+pub fn difficulty_string(difficulty: i16) -> &'static str {
+    match difficulty {
+        0 => "Easy",
+        1 => "Normal",
+        2 => "Heroic",
+        3 => "Legendary",
+        _ => "UNKNOWN"
+    }
+}
+
 // Application
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct EngineSnapshot {
     pub map_name: String,
     pub player_header: EntityManager<PlayerDataEntry>,
@@ -246,6 +264,8 @@ pub struct EngineSnapshot {
     pub object_header: EntityManager<ObjectHeaderEntry>,
     pub object_header_entries: Vec<Option<ObjectHeaderEntry>>, 
     pub object_entries: Vec<Option<Object>>,
+    // The header in front of every object, kept along with the object.
+    pub object_list_headers: Vec<Option<ObjectListHeader>>,
     pub player_globals: PlayersGlobals,
     pub game_globals: GameGlobals,
     pub game_time_globals: GameTimeGlobals,
@@ -254,6 +274,19 @@ pub struct EngineSnapshot {
 }
 
 impl EngineSnapshot {
+    // Header entry and object in slot `index`, None when the slot is free or outside the table.
+    pub fn slot(&self, index: usize) -> Option<(&ObjectHeaderEntry, &Object)> {
+        let header_entry = self.object_header_entries.get(index)?.as_ref()?;
+        let object = self.object_entries.get(index)?.as_ref()?;
+        Some((header_entry, object))
+    }
+
+    pub fn first_free_index(&self) -> usize {
+        // Find the first free entry in the object list?
+        // Sometimes the next_object_index in the object_pool_header is not consistent with the next free entry in the object pool ???????????
+        self.object_entries.iter().position(|entry| entry.is_none()).unwrap_or(0)
+    }
+
     // We only check the index here as the game does not safely check the ID
     // Otherwise, during some AUP we would not see the proper assignments for players.
     pub fn find_local_player_index_from_unit_index(&self, unit_index: u16) -> Option<u16> {
@@ -285,23 +318,71 @@ impl EngineSnapshot {
 
 }
 
-pub fn build_snapshot(memory: &Memory) -> Option<EngineSnapshot> {
-    let tag_header: TagHeader = memory.read(HALO_TAG_HEADER_ADDR);
-    if !tag_header.is_valid() { return None; }
+// Confirms that `memory` holds Halo's guest RAM rather than some other allocation of the same size.
+pub fn is_halo_memory(memory: &Memory) -> bool {
+    let tag_header = memory.read::<TagHeader>(HALO_TAG_HEADER_ADDR);
+    let object_manager = memory.read::<EntityManager<ObjectHeaderEntry>>(HALO_OBJECT_POOL_HEADER_ADDR);
+
+    tag_header.is_ok_and(|tag_header| tag_header.is_valid())
+        && object_manager.is_ok_and(|object_manager| object_manager.is_signature_valid())
+}
+
+// Just the game tick, cheap enough to poll for the next tick without building a whole snapshot.
+pub fn read_local_time(memory: &Memory) -> Result<u32, MemoryError> {
+    memory.read::<GameTimeGlobals>(HALO_GAME_TIME_GLOBALS).map(|game_time_globals| game_time_globals.local_time)
+}
 
-    let game_globals: GameGlobals = memory.read(HALO_GAME_GLOBALS_ADDR);
-    if !game_globals.game_options.is_valid() { return None; }
+pub fn build_snapshot(memory: &Memory) -> Result<EngineSnapshot, SnapshotError> {
+    let tag_header: TagHeader = memory.read(HALO_TAG_HEADER_ADDR)?;
+    let game_globals: GameGlobals = memory.read(HALO_GAME_GLOBALS_ADDR)?;
 
-    let object_manager: EntityManager<ObjectHeaderEntry> = memory.read(HALO_OBJECT_POOL_HEADER_ADDR);
-    if !object_manager.is_valid() { return None; }
+    // The headers are in flux while a map loads, so report the progress rather than whichever check trips first.
+    let loading_or = |error: SnapshotError| {
+        if game_globals.map_loading != 0 {
+            SnapshotError::MapLoading { progress: game_globals.map_load_progress }
+        } else {
+            error
+        }
+    };
 
-    let player_manager: EntityManager<PlayerDataEntry> = memory.read(HALO_PLAYER_POOL_HEADER_ADDR);
-    if !player_manager.is_valid() { return None; }
+    if !tag_header.is_valid() {
+        return Err(loading_or(SnapshotError::InvalidTagHeader { address: HALO_TAG_HEADER_ADDR, footer: tag_header.footer }));
+    }
 
-    let object_pool_entries = object_manager.read(memory);
-    let player_pool_entries = player_manager.read(memory);
+    if !game_globals.game_options.is_valid() {
+        let address = HALO_GAME_GLOBALS_ADDR + offset_of!(GameGlobals, game_options) as u32;
+        return Err(loading_or(SnapshotError::InvalidGameOptions { address, difficulty: game_globals.game_options.difficulty }));
+    }
+
+    let object_manager: EntityManager<ObjectHeaderEntry> = memory.read(HALO_OBJECT_POOL_HEADER_ADDR)?;
+    if !object_manager.is_valid() {
+        return Err(loading_or(SnapshotError::InvalidPool {
+            name: "object",
+            address: HALO_OBJECT_POOL_HEADER_ADDR,
+            signature: object_manager.signature,
+            valid: object_manager.valid,
+            capacity: object_manager.capacity,
+            max_entries: object_manager.max_entries
+        }));
+    }
+
+    let player_manager: EntityManager<PlayerDataEntry> = memory.read(HALO_PLAYER_POOL_HEADER_ADDR)?;
+    if !player_manager.is_valid() {
+        return Err(loading_or(SnapshotError::InvalidPool {
+            name: "player",
+            address: HALO_PLAYER_POOL_HEADER_ADDR,
+            signature: player_manager.signature,
+            valid: player_manager.valid,
+            capacity: player_manager.capacity,
+            max_entries: player_manager.max_entries
+        }));
+    }
+
+    let object_pool_entries = object_manager.read(memory)?;
+    let player_pool_entries = player_manager.read(memory)?;
 
     let mut game_object_entries: Vec<_> = (0..object_manager.max_entries).map(|_| None).collect();
+    let mut object_list_headers: Vec<_> = (0..object_manager.max_entries).map(|_| None).collect();
     for index in 0..object_manager.capacity as usize {
         let object_entry = &object_pool_entries[index];
         if object_entry.is_none() { continue; }
@@ -310,20 +391,26 @@ pub fn build_snapshot(memory: &Memory) -> Option<EngineSnapshot> {
         let object_address = Memory::fix_pointer(object_entry.object_address);
         if object_address != 0 && object_address >= size_of::<ObjectListHeader>() as u32 {
             let object_list_header_ptr = object_address - size_of::<ObjectListHeader>() as u32;
-            let object_list_header: ObjectListHeader = memory.read(object_list_header_ptr);
+            let object_list_header: ObjectListHeader = memory.read(object_list_header_ptr)?;
 
             if !(object_list_header.header_head == DEAH && object_list_header.header_tail == LIAT) {
-                return None;
+                return Err(loading_or(SnapshotError::CorruptObjectHeader {
+                    index,
+                    address: object_list_header_ptr,
+                    head: object_list_header.header_head,
+                    tail: object_list_header.header_tail
+                }));
             }
 
-            let game_object: Object = memory.read(object_address);
+            let game_object: Object = memory.read(object_address)?;
             game_object_entries[index] = Some(game_object);
+            object_list_headers[index] = Some(object_list_header);
         }
     }
 
     // TODO: Find a way to sanity check this data
-    let player_globals: PlayersGlobals = memory.read(HALO_PLAYER_GLOBALS_ADDR);
-    let game_time_globals: GameTimeGlobals = memory.read(HALO_GAME_TIME_GLOBALS);
+    let player_globals: PlayersGlobals = memory.read(HALO_PLAYER_GLOBALS_ADDR)?;
+    let game_time_globals: GameTimeGlobals = memory.read(HALO_GAME_TIME_GLOBALS)?;
 
     let mut map_name = String::default();
     if game_globals.map_loaded == 1 {
@@ -339,32 +426,38 @@ pub fn build_snapshot(memory: &Memory) -> Option<EngineSnapshot> {
     let mut tag_index_to_str: HashMap<u32, String> = HashMap::new();
 
     let tag_array_base_ptr = Memory::fix_pointer(tag_header.tag_array_ptr);
+    memory.prefetch(tag_array_base_ptr, size_of::<TagEntry>() * tag_header.tag_count as usize)?;
+
     for index in 0..tag_header.tag_count {
-        let tag_entry_ptr = tag_array_base_ptr + (size_of::<TagEntry>() as u32 * index);
-        let tag_entry: TagEntry = memory.read(tag_entry_ptr);
+        let tag_entry_ptr = entry_address(tag_array_base_ptr, size_of::<TagEntry>() as u32, index)?;
+        let tag_entry: TagEntry = memory.read(tag_entry_ptr)?;
 
         // Also used for tag_index_to_tag_entry as both will be treated seperately but same.
-        if !tag_index_to_str.contains_key(&tag_entry.tag_index) {
+        if let Entry::Vacant(tag_str) = tag_index_to_str.entry(tag_entry.tag_index) {
             let tag_path_ptr = Memory::fix_pointer(tag_entry.tag_path_ptr);
 
-            if let Ok(value) = memory.read_str(tag_path_ptr) {
-                tag_index_to_str.insert(tag_entry.tag_index, value.to_string());
+            // Paths that are not valid UTF-8 are shown as unknown, anything else means the tag array is corrupt.
+            match memory.read_str(tag_path_ptr) {
+                Ok(value) => { tag_str.insert(value); }
+                Err(MemoryError::InvalidUtf8 { .. }) => {}
+                Err(e) => return Err(e.into())
             }
 
             tag_index_to_tag_entry.insert(tag_entry.tag_index, tag_entry);
         }
     }
 
-    Some(EngineSnapshot {
-        map_name: map_name,
+    Ok(EngineSnapshot {
+        map_name,
         object_header: object_manager,
         object_header_entries: object_pool_entries,
         object_entries: game_object_entries,
+        object_list_headers,
         player_header: player_manager,
         player_entries: player_pool_entries,
-        player_globals: player_globals,
-        game_globals: game_globals,
-        game_time_globals: game_time_globals,
+        player_globals,
+        game_globals,
+        game_time_globals,
         tags: tag_index_to_str,
         tag_entries: tag_index_to_tag_entry
     })
@@ -1,12 +1,24 @@
 use std::marker::PhantomData;
 
-use crate::memory::Memory;
+use serde::Serialize;
+
+use crate::memory::{Memory, MemoryError};
+
+use super::serialize;
 
 const AT_T_AT_D: u32 = 1681945664;
 
-#[derive(Debug)]
+// Address of entry `index` in an array of `size` byte entries, corrupt headers can point it past the 32-bit address space.
+pub fn entry_address(base: u32, size: u32, index: u32) -> Result<u32, MemoryError> {
+    size.checked_mul(index)
+        .and_then(|offset| base.checked_add(offset))
+        .ok_or(MemoryError::OutOfRange { address: base, length: size as usize })
+}
+
+#[derive(Debug, Clone, Serialize)]
 #[repr(C)]
 pub struct EntityManager<T> {
+    #[serde(serialize_with = "serialize::c_string")]
     pub name: [u8; 32],
     pub max_entries: u16,
     pub data_sizeof: u16,
@@ -19,32 +31,94 @@ pub struct EntityManager<T> {
     pub size: u16,
     pub next_id: u16,
     pub data_begin: u32,
+    #[serde(skip)]
     _phantom: PhantomData<T>
 }
 
 impl<T> EntityManager<T> {
-    fn is_signature_valid(&self) -> bool {
+    pub fn is_signature_valid(&self) -> bool {
         self.signature == AT_T_AT_D
     }
 
+    // Entries are stored for max_entries slots but walked up to capacity, a larger capacity means the header is garbage.
     pub fn is_valid(&self) -> bool {
-        self.is_signature_valid() && self.valid == 1
+        self.is_signature_valid() && self.valid == 1 && self.capacity <= self.max_entries
     }
 
-    pub fn read(&self, memory: &Memory) -> Vec<Option<T>> {
+    pub fn read(&self, memory: &Memory) -> Result<Vec<Option<T>>, MemoryError> {
         let mut entries: Vec<_> = (0..self.max_entries).map(|_| None).collect();
 
         let data_begin = Memory::fix_pointer(self.data_begin);
-        for index in (0..self.capacity as usize).rev() {
-            let index_ptr_addr = data_begin + (self.data_sizeof as u32 * index as u32);
-            let id: u16 = memory.read(index_ptr_addr);
+        memory.prefetch(data_begin, self.data_sizeof as usize * self.capacity as usize)?;
+
+        for index in (0..self.capacity.min(self.max_entries) as usize).rev() {
+            let index_ptr_addr = entry_address(data_begin, self.data_sizeof as u32, index as u32)?;
+            let id: u16 = memory.read(index_ptr_addr)?;
 
             if id != 0 || self.identifier_zero_invalid == 0 { 
-                let entry: T = memory.read(index_ptr_addr);
+                let entry: T = memory.read(index_ptr_addr)?;
                 entries[index] = Some(entry);
             }
         }
 
-        entries
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::memory::BufferSource;
+
+    fn pool(capacity: u16, max_entries: u16, data_begin: u32) -> EntityManager<u32> {
+        EntityManager {
+            name: [0; 32],
+            max_entries,
+            data_sizeof: 4,
+            valid: 1,
+            identifier_zero_invalid: 1,
+            unknown_1: 0,
+            signature: AT_T_AT_D,
+            next_index: 0,
+            capacity,
+            size: 0,
+            next_id: 0,
+            data_begin,
+            _phantom: PhantomData
+        }
+    }
+
+    #[test]
+    fn entry_address_steps_by_entry_size() {
+        assert_eq!(entry_address(0x1000, 0x10, 0), Ok(0x1000));
+        assert_eq!(entry_address(0x1000, 0x10, 3), Ok(0x1030));
+    }
+
+    #[test]
+    fn entry_address_overflow_is_out_of_range() {
+        assert_eq!(entry_address(0xFFFF_FF00, 0x100, 1), Err(MemoryError::OutOfRange { address: 0xFFFF_FF00, length: 0x100 }));
+        assert_eq!(entry_address(0, 0x1_0000, 0x1_0000), Err(MemoryError::OutOfRange { address: 0, length: 0x1_0000 }));
+    }
+
+    #[test]
+    fn capacity_past_max_entries_is_invalid() {
+        assert!(pool(4, 4, 0).is_valid());
+        assert!(!pool(5, 4, 0).is_valid());
+    }
+
+    #[test]
+    fn entries_are_read_up_to_capacity() {
+        // Slots 0 and 2 are in use, slot 1 has a zero ID and slot 3 is past the capacity.
+        let bytes: Vec<u8> = [7u32, 0, 9, 11].iter().flat_map(|entry| entry.to_le_bytes()).collect();
+        let memory = Memory::new(BufferSource::new(bytes));
+
+        assert_eq!(pool(3, 4, 0x8000_0000).read(&memory), Ok(vec![Some(7), None, Some(9), None]));
+    }
+
+    #[test]
+    fn entries_past_the_end_of_memory_fail() {
+        let memory = Memory::new(BufferSource::zeroed(8));
+        assert!(matches!(pool(4, 4, 0).read(&memory), Err(MemoryError::OutOfRange { .. })));
     }
 }
@@ -0,0 +1,45 @@
+use std::{error::Error, fmt};
+
+use crate::memory::MemoryError;
+
+// Why build_snapshot could not produce a snapshot, along with the offending address and value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotError {
+    Memory(MemoryError),
+    MapLoading { progress: f32 },
+    InvalidTagHeader { address: u32, footer: u32 },
+    InvalidGameOptions { address: u32, difficulty: i16 },
+    InvalidPool { name: &'static str, address: u32, signature: u32, valid: u8, capacity: u16, max_entries: u16 },
+    CorruptObjectHeader { index: usize, address: u32, head: u32, tail: u32 }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Memory(e) =>
+                write!(f, "memory read failed: {}", e),
+            SnapshotError::MapLoading { progress } =>
+                write!(f, "map loading {:.0}%", progress * 100.0),
+            SnapshotError::InvalidTagHeader { address, footer } =>
+                write!(f, "tag header invalid at {:#010x} (footer {:#010x})", address, footer),
+            SnapshotError::InvalidGameOptions { address, difficulty } =>
+                write!(f, "game options invalid at {:#010x} (difficulty {})", address, difficulty),
+            SnapshotError::InvalidPool { name, address, signature, valid, capacity, max_entries } =>
+                write!(
+                    f,
+                    "{} pool header invalid at {:#010x} (signature {:#010x}, valid {}, capacity {} of {})",
+                    name, address, signature, valid, capacity, max_entries
+                ),
+            SnapshotError::CorruptObjectHeader { index, address, head, tail } =>
+                write!(f, "object header corrupt at index {} ({:#010x}, head {:#010x}, tail {:#010x})", index, address, head, tail)
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+impl From<MemoryError> for SnapshotError {
+    fn from(e: MemoryError) -> Self {
+        SnapshotError::Memory(e)
+    }
+}
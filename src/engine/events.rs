@@ -0,0 +1,94 @@
+use super::{diff_player_globals, object_type_string, Datum, EngineSnapshot, FieldChange, PlayerDataEntry, LOCAL_DEAD_PLAYERS};
+
+// Field of PlayerUnitChanged for the unit a dead player respawns from.
+pub const LOCAL_DEAD_PLAYER: &str = "Local Dead Player";
+
+#[derive(Debug, Clone)]
+pub struct ObjectSummary {
+    pub index: usize,
+    pub id: u16,
+    pub tag: String,
+    pub object_type: &'static str,
+    pub position: [f32; 3]
+}
+
+#[derive(Debug, Clone)]
+pub enum EventKind {
+    ObjectCreated(ObjectSummary),
+    ObjectDeleted(ObjectSummary),
+    PlayerUnitChanged { player: usize, field: &'static str, before: Datum, after: Datum },
+    PlayerGlobalsChanged(FieldChange)
+}
+
+#[derive(Debug, Clone)]
+pub struct SnapshotEvent {
+    pub tick: u32,
+    pub kind: EventKind
+}
+
+fn object_summary(snapshot: &EngineSnapshot, index: usize) -> Option<ObjectSummary> {
+    let (header_entry, object) = snapshot.slot(index)?;
+
+    Some(ObjectSummary {
+        index,
+        id: header_entry.id,
+        tag: snapshot.tags.get(&object.tag_index).cloned().unwrap_or_else(|| "UNKNOWN".to_string()),
+        object_type: object_type_string(header_entry.data_type),
+        position: object.position
+    })
+}
+
+// Current and last unit of a player, a player entry that is not in use counts as having neither.
+fn player_units(entry: Option<&PlayerDataEntry>) -> (Datum, Datum) {
+    match entry {
+        Some(entry) => (entry.slave_unit_index.clone(), entry.last_slave_unit_index.clone()),
+        None => (Datum::from_raw(u32::MAX), Datum::from_raw(u32::MAX))
+    }
+}
+
+// What happened between two consecutive snapshots of the same map, stamped with the tick of `after`.
+pub fn snapshot_events(before: &EngineSnapshot, after: &EngineSnapshot) -> Vec<SnapshotEvent> {
+    let mut kinds = Vec::new();
+
+    let slot_count = before.object_entries.len().max(after.object_entries.len());
+    for index in 0..slot_count {
+        let slot_id = |snapshot: &EngineSnapshot| snapshot.slot(index).map(|(header_entry, _)| header_entry.id);
+        let (id_before, id_after) = (slot_id(before), slot_id(after));
+        // Deleted and created again between the two snapshots when the ID changed.
+        if id_before.is_some() && id_before != id_after {
+            kinds.extend(object_summary(before, index).map(EventKind::ObjectDeleted));
+        }
+        if id_after.is_some() && id_before != id_after {
+            kinds.extend(object_summary(after, index).map(EventKind::ObjectCreated));
+        }
+    }
+
+    let player_count = before.player_entries.len().max(after.player_entries.len());
+    for player in 0..player_count {
+        let (unit_before, last_unit_before) = player_units(before.player_entries.get(player).and_then(Option::as_ref));
+        let (unit_after, last_unit_after) = player_units(after.player_entries.get(player).and_then(Option::as_ref));
+
+        for (field, before, after) in [("Slave Unit", unit_before, unit_after), ("Last Slave Unit", last_unit_before, last_unit_after)] {
+            if before != after {
+                kinds.push(EventKind::PlayerUnitChanged { player, field, before, after });
+            }
+        }
+    }
+
+    // Reported per player like the units above, rather than as one change to the whole array.
+    let dead_players = before.player_globals.local_dead_players.iter().zip(&after.player_globals.local_dead_players);
+    for (player, (before, after)) in dead_players.enumerate() {
+        if before != after {
+            kinds.push(EventKind::PlayerUnitChanged { player, field: LOCAL_DEAD_PLAYER, before: before.clone(), after: after.clone() });
+        }
+    }
+
+    kinds.extend(
+        diff_player_globals(&before.player_globals, &after.player_globals).into_iter()
+            .filter(|change| change.name != LOCAL_DEAD_PLAYERS)
+            .map(EventKind::PlayerGlobalsChanged)
+    );
+
+    let tick = after.game_time_globals.local_time;
+    kinds.into_iter().map(|kind| SnapshotEvent { tick, kind }).collect()
+}
@@ -1,7 +1,15 @@
+#[allow(clippy::module_inception)]
 mod engine;
 mod datum;
+mod diff;
 mod entity;
+mod error;
+mod events;
+mod serialize;
 
 pub use engine::*;
 pub use datum::*;
-pub use entity::*;
\ No newline at end of file
+pub use diff::*;
+pub use entity::*;
+pub use error::*;
+pub use events::*;
\ No newline at end of file
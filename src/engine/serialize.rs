@@ -0,0 +1,19 @@
+use serde::Serializer;
+
+// Helpers for `#[serde(serialize_with)]` on the raw engine structs, serde only handles arrays of up to 32 elements itself.
+
+pub fn bytes<S: Serializer, const N: usize>(bytes: &[u8; N], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bytes(bytes)
+}
+
+// A NUL terminated string in a fixed size buffer, e.g. the map name in GameOptions.
+pub fn c_string<S: Serializer, const N: usize>(bytes: &[u8; N], serializer: S) -> Result<S::Ok, S::Error> {
+    let length = bytes.iter().position(|byte| *byte == 0).unwrap_or(N);
+    serializer.serialize_str(&String::from_utf8_lossy(&bytes[..length]))
+}
+
+// A NUL terminated UTF-16 string in a fixed size buffer, e.g. player names.
+pub fn utf16_string<S: Serializer, const N: usize>(chars: &[u16; N], serializer: S) -> Result<S::Ok, S::Error> {
+    let length = chars.iter().position(|char| *char == 0).unwrap_or(N);
+    serializer.serialize_str(&String::from_utf16_lossy(&chars[..length]))
+}
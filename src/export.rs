@@ -0,0 +1,93 @@
+use std::{fs::File, io::{self, BufWriter, Write}, path::Path};
+
+use crate::engine::{object_type_string, Datum, EngineSnapshot};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    Json,
+    MessagePack
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::MessagePack => "msgpack"
+        }
+    }
+}
+
+// Write the whole snapshot to `path`, MessagePack keeps the field names so both formats read the same.
+pub fn export_snapshot(snapshot: &EngineSnapshot, path: impl AsRef<Path>, format: ExportFormat) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    match format {
+        ExportFormat::Json => serde_json::to_writer_pretty(&mut writer, snapshot).map_err(io::Error::other)?,
+        ExportFormat::MessagePack => rmp_serde::encode::write_named(&mut writer, snapshot).map_err(io::Error::other)?
+    }
+
+    writer.flush()
+}
+
+pub const OBJECTS_CSV_HEADER: &str = "tick,datum,index,id,player,dead_player,x,y,z,tag,type";
+
+// Quote a CSV field if it would otherwise break the row.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// The rows of the Objects table, one line per slot. Free slots only carry their index.
+pub fn write_object_rows(writer: &mut impl Write, snapshot: &EngineSnapshot, include_free: bool) -> io::Result<()> {
+    let tick = snapshot.game_time_globals.local_time;
+
+    for index in 0..snapshot.object_header.max_entries as usize {
+        let entries = (&snapshot.object_header_entries[index], &snapshot.object_entries[index]);
+        let (Some(object_pool_entry), Some(game_object_entry)) = entries else {
+            if include_free {
+                writeln!(writer, "{},,{},,,,,,,,", tick, index)?;
+            }
+            continue;
+        };
+
+        let datum_handle = Datum::from_parts(index as u16, object_pool_entry.id);
+        let player = snapshot.find_local_player_index_from_unit_index(index as u16)
+            .map(|player_index| player_index.to_string())
+            .unwrap_or_default();
+        let dead_player = snapshot.find_next_object_datum_player(datum_handle.clone())
+            .map(|player_index| player_index.to_string())
+            .unwrap_or_default();
+        let tag = snapshot.tags.get(&game_object_entry.tag_index).map(String::as_str).unwrap_or("UNKNOWN");
+        let [x, y, z] = game_object_entry.position;
+
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            tick,
+            datum_handle.get_handle(),
+            index,
+            object_pool_entry.id,
+            player,
+            dead_player,
+            x,
+            y,
+            z,
+            csv_field(tag),
+            object_type_string(object_pool_entry.data_type)
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn export_objects_csv(snapshot: &EngineSnapshot, path: impl AsRef<Path>, include_free: bool) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    writeln!(writer, "{}", OBJECTS_CSV_HEADER)?;
+    write_object_rows(&mut writer, snapshot, include_free)?;
+
+    writer.flush()
+}
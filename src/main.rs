@@ -1,20 +1,36 @@
 #![allow(dead_code)]
+mod attach;
+mod cli;
 mod engine;
+mod export;
 mod memory;
+mod recording;
+mod sampler;
+mod script;
+mod server;
+mod session;
+mod ui;
 
-use std::ffi::OsStr;
+use std::{io, path::Path, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
 
-use engine::{build_snapshot, object_type_string, Datum, EngineSnapshot};
+use attach::{attempt_due, find_guest_memory, find_xemu_processes};
+
+use engine::{difficulty_string, object_type_string, Datum, EngineSnapshot};
+use export::{export_objects_csv, export_snapshot, ExportFormat};
 use glow::HasContext;
-use imgui::{Condition, Context, TableBgTarget, TableFlags, Ui};
+use imgui::{Condition, Context, SelectableFlags, SliderFlags, TabItemFlags, TableBgTarget, TableFlags, Ui, WindowFlags, WindowFocusedFlags};
 use imgui_glow_renderer::{glow, AutoRenderer};
 use imgui_sdl2_support::SdlPlatform;
-use memory::Memory;
+use memory::{DumpSource, Memory, ProcessSource, XBOX_MEMORY_SIZE};
+use recording::Replay;
+use sampler::{SampleRate, SAMPLE_RATES};
 use sdl2::{
     event::Event,
     video::{GLProfile, Window},
 };
-use sysinfo::System;
+use server::Server;
+use session::Session;
+use ui::{DiffWindow, EventsWindow, ObjectInspector, PathDialog, ScriptConsole, ServerWindow};
 
 static GREEN: [f32; 4] = [0.69, 0.87, 0.15, 1.0];
 static RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
@@ -22,10 +38,133 @@ static ORANGE: [f32; 4] = [0.97, 0.7, 0.17, 1.0];
 static DARK_GREY: [f32; 4] = [0.14, 0.14, 0.14, 1.0];
 static WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
 
+// How long a notification stays on screen.
+const NOTIFICATION_DURATION: Duration = Duration::from_secs(5);
+
+// Width of the Players Globals window when it sits beside the Objects window.
+const PLAYERS_WINDOW_WIDTH: f32 = 450.0;
+
+struct Notification {
+    message: String,
+    created: Instant
+}
+
+#[derive(Default)]
+struct Notifications(Vec<Notification>);
+
+impl Notifications {
+    fn push(&mut self, message: String) {
+        self.0.push(Notification { message, created: Instant::now() });
+    }
+
+    fn draw(&mut self, ui: &Ui) {
+        self.0.retain(|notification| notification.created.elapsed() < NOTIFICATION_DURATION);
+
+        let width = ui.io().display_size[0];
+        let height = ui.io().display_size[1];
+
+        // Stack the notifications upwards from the bottom right corner.
+        let mut offset = 10.0;
+        for (index, notification) in self.0.iter().enumerate() {
+            let notification_window = ui.window(format!("##Notification{}", index))
+                .position([width - 10.0, height - offset], Condition::Always)
+                .position_pivot([1.0, 1.0])
+                .bg_alpha(0.85)
+                .flags(WindowFlags::NO_DECORATION | WindowFlags::ALWAYS_AUTO_RESIZE | WindowFlags::NO_MOVE
+                    | WindowFlags::NO_SAVED_SETTINGS | WindowFlags::NO_FOCUS_ON_APPEARING | WindowFlags::NO_NAV)
+                .begin();
+
+            if let Some(notification_window) = notification_window {
+                ui.text_colored(RED, &notification.message);
+                offset += ui.window_size()[1] + 5.0;
+                notification_window.end();
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum Layout {
+    // One session at a time, picked from the tab bar in the menu.
+    Tabs,
+    // Every session in its own column, Objects above Players Globals.
+    SideBySide
+}
+
 struct DrawContext {
-    memory: Option<Memory>,
+    sessions: Vec<Session>,
+    active_session: usize,
+    // Bring the active session to the front of the tab bar, ImGui otherwise keeps the tab it had selected.
+    select_active_tab: bool,
+    next_session_id: usize,
+    layout: Layout,
+    sample_rate: SampleRate,
+    lock_scrolling: bool,
+    shared_scroll: Option<f32>,
+    show_attach: bool,
     virtual_address: String,
-    target_index: u32
+    auto_attach: bool,
+    selected_pid: Option<u32>,
+    last_attach_attempt: Option<Instant>,
+    open_dump_dialog: PathDialog,
+    save_dump_dialog: PathDialog,
+    record_dialog: PathDialog,
+    record_objects_csv: bool,
+    record_objects_csv_path: String,
+    open_recording_dialog: PathDialog,
+    export_dialog: PathDialog,
+    export_format: ExportFormat,
+    export_csv_dialog: PathDialog,
+    export_csv_include_free: bool,
+    server: Option<Server>,
+    server_window: ServerWindow,
+    diff_window: DiffWindow,
+    events_window: EventsWindow,
+    script_console: ScriptConsole,
+    object_inspector: ObjectInspector,
+    notifications: Notifications
+}
+
+impl DrawContext {
+    fn add_session(&mut self, name: String, memory: Memory, pid: Option<u32>) {
+        let mut session = Session::new(self.next_session_id, name, memory, pid, self.sample_rate);
+        session.script_engine = Some(self.script_console.engine());
+        self.sessions.push(session);
+        self.next_session_id += 1;
+        self.active_session = self.sessions.len() - 1;
+        self.select_active_tab = true;
+    }
+
+    fn add_replay(&mut self, name: String, replay: Replay) {
+        let mut session = Session::from_replay(self.next_session_id, name, replay, self.sample_rate);
+        session.script_engine = Some(self.script_console.engine());
+        self.sessions.push(session);
+        self.next_session_id += 1;
+        self.active_session = self.sessions.len() - 1;
+        self.select_active_tab = true;
+    }
+
+    fn active_session(&self) -> Option<&Session> {
+        self.sessions.get(self.active_session)
+    }
+
+    fn attached_pids(&self) -> Vec<u32> {
+        self.sessions.iter().filter_map(|session| session.pid).collect()
+    }
+
+    // Search `pid` for the guest memory at most once per ATTACH_RETRY_INTERVAL and open a session once found.
+    fn try_attach(&mut self, pid: u32) {
+        if !attempt_due(&mut self.last_attach_attempt) {
+            return;
+        }
+
+        if let Some((virtual_address, memory)) = find_guest_memory(pid) {
+            self.virtual_address = format!("{:x}", virtual_address);
+            self.add_session(format!("xemu {}", pid), memory, Some(pid));
+            self.selected_pid = None;
+            self.show_attach = false;
+        }
+    }
 }
 
 // Create a new glow context.
@@ -55,12 +194,12 @@ fn print_player(ui: &Ui, snapshot: &EngineSnapshot, player_index: u16) {
             }
     
             if !found {
-                ui.text_colored(ORANGE, format!("Position: None"));
+                ui.text_colored(ORANGE, "Position: None");
             }
         }
     } else { 
-        ui.text_colored(ORANGE, format!("Unit Handle: None"));
-        ui.text_colored(ORANGE, format!("Position: None"));
+        ui.text_colored(ORANGE, "Unit Handle: None");
+        ui.text_colored(ORANGE, "Position: None");
     }
 
     let handle = &snapshot.player_globals.local_dead_players[player_index as usize];
@@ -76,219 +215,658 @@ fn print_player(ui: &Ui, snapshot: &EngineSnapshot, player_index: u16) {
         }
 
         if !found {
-            ui.text_colored(ORANGE, format!("Next Datum Position: None"));
+            ui.text_colored(ORANGE, "Next Datum Position: None");
         }
     }
 
-    if let Some(_) = snapshot.player_entries[player_index as usize].as_ref() {
-    } else { 
-        ui.text_colored(ORANGE, format!("Last Unit Handle: None")) 
+    if snapshot.player_entries[player_index as usize].is_none() {
+        ui.text_colored(ORANGE, "Last Unit Handle: None") 
     }
 }
 
-fn draw(ui: &mut Ui, should_exit: &mut bool, draw_context: &mut DrawContext) {
-    let memory = draw_context.memory.as_mut().unwrap();
-    memory.refresh();
-    let snapshot = build_snapshot(memory);
+fn draw_open_dump(ui: &Ui, draw_context: &mut DrawContext) {
+    let Some(path) = draw_context.open_dump_dialog.draw(ui, |_, _| {}) else { return; };
 
-    // Do not render anything if the snapshot is invalid.
-    let width = ui.io().display_size[0];
-    let height = ui.io().display_size[1];
+    let result = match DumpSource::open(&path) {
+        Ok(dump) => {
+            let name = Path::new(&path).file_name()
+                .map(|file_name| file_name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.clone());
 
-    let mut first_free_index = 0;
-    if let Some(snapshot) = &snapshot {
-        // Find the first free entry in the object list?
-        // Sometimes the next_object_index in the object_pool_header is not consistent with the next free entry in the object pool ???????????
-        for (index, entry) in snapshot.object_entries.iter().enumerate() {
-            if entry.is_none() {
-                first_free_index = index;
-                break;
+            draw_context.add_session(name, Memory::new(dump), None);
+            Ok(None)
+        }
+        Err(e) => Err(format!("Could not open dump: {}", e))
+    };
+    draw_context.open_dump_dialog.finish(result);
+}
+
+fn draw_open_recording(ui: &Ui, draw_context: &mut DrawContext) {
+    let Some(path) = draw_context.open_recording_dialog.draw(ui, |_, _| {}) else { return; };
+
+    let result = match Replay::open(&path) {
+        Ok(replay) => {
+            let name = Path::new(&path).file_name()
+                .map(|file_name| file_name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.clone());
+
+            draw_context.add_replay(name, replay);
+            Ok(None)
+        }
+        Err(e) => Err(format!("Could not open recording: {}", e))
+    };
+    draw_context.open_recording_dialog.finish(result);
+}
+
+fn draw_save_dump(ui: &Ui, draw_context: &mut DrawContext) {
+    let Some(path) = draw_context.save_dump_dialog.draw(ui, |_, _| {}) else { return; };
+
+    let result = match draw_context.active_session() {
+        Some(session) => match session.save_dump(&path) {
+            Ok(()) => Ok(Some(format!("Saved dump to {}", path))),
+            Err(e) => Err(format!("Could not save dump: {}", e))
+        },
+        None => Err("Could not save dump: not attached".to_string())
+    };
+    draw_context.save_dump_dialog.finish(result);
+}
+
+fn draw_export(ui: &Ui, draw_context: &mut DrawContext) {
+    let export_format = &mut draw_context.export_format;
+    let Some(path) = draw_context.export_dialog.draw(ui, |ui, path| {
+        for (label, format) in [("JSON", ExportFormat::Json), ("MessagePack", ExportFormat::MessagePack)] {
+            if ui.radio_button_bool(label, *export_format == format) {
+                // Keep the extension in line with the format unless the user picked their own.
+                let previous_extension = format!(".{}", export_format.extension());
+                if let Some(stem) = path.strip_suffix(&previous_extension) {
+                    *path = format!("{}.{}", stem, format.extension());
+                }
+                *export_format = format;
             }
+            ui.same_line();
+        }
+        ui.new_line();
+    }) else { return; };
+
+    let result = match draw_context.active_session().and_then(|session| session.snapshot.as_ref()) {
+        Some(snapshot) => match export_snapshot(snapshot, &path, draw_context.export_format) {
+            Ok(()) => Ok(Some(format!("Exported snapshot to {}", path))),
+            Err(e) => Err(format!("Could not export snapshot: {}", e))
+        },
+        None => Err("Could not export snapshot: nothing to show".to_string())
+    };
+    draw_context.export_dialog.finish(result);
+}
+
+fn draw_export_csv(ui: &Ui, draw_context: &mut DrawContext) {
+    let include_free = &mut draw_context.export_csv_include_free;
+    let Some(path) = draw_context.export_csv_dialog.draw(ui, |ui, _| {
+        ui.checkbox("Include free slots", include_free);
+    }) else { return; };
+
+    let result = match draw_context.active_session().and_then(|session| session.snapshot.as_ref()) {
+        Some(snapshot) => match export_objects_csv(snapshot, &path, draw_context.export_csv_include_free) {
+            Ok(()) => Ok(Some(format!("Exported objects to {}", path))),
+            Err(e) => Err(format!("Could not export objects: {}", e))
+        },
+        None => Err("Could not export objects: nothing to show".to_string())
+    };
+    draw_context.export_csv_dialog.finish(result);
+}
+
+fn draw_record(ui: &Ui, draw_context: &mut DrawContext) {
+    let objects_csv = &mut draw_context.record_objects_csv;
+    let objects_csv_path = &mut draw_context.record_objects_csv_path;
+    let Some(path) = draw_context.record_dialog.draw(ui, |ui, _| {
+        ui.checkbox("Append the Objects table of every tick to a CSV", objects_csv);
+        if *objects_csv {
+            ui.input_text("Path to write CSV", objects_csv_path)
+                .allow_tab_input(false)
+                .build();
+        }
+    }) else { return; };
+
+    let objects_csv = draw_context.record_objects_csv.then_some(draw_context.record_objects_csv_path.as_str());
+    let result = match draw_context.sessions.get_mut(draw_context.active_session) {
+        Some(session) => session.start_recording(&path, objects_csv),
+        None => Err(io::Error::new(io::ErrorKind::NotConnected, "not attached"))
+    };
+    draw_context.record_dialog.finish(result.map(|()| None).map_err(|e| format!("Could not start recording: {}", e)));
+}
+
+// Pick up the latest snapshot of every session, dropping dead emulators into the waiting state until xemu comes back.
+fn update_sessions(draw_context: &mut DrawContext) {
+    let attached_pids = draw_context.attached_pids();
+
+    for (index, session) in draw_context.sessions.iter_mut().enumerate() {
+        let other_pids: Vec<u32> = attached_pids.iter().copied().filter(|pid| Some(*pid) != session.pid).collect();
+
+        for message in session.update(&other_pids) {
+            draw_context.notifications.push(message);
+        }
+
+        let snapshot_changed = session.take_snapshot_changed();
+        if let (true, Some(server), Some(snapshot)) = (snapshot_changed, &draw_context.server, &session.snapshot) {
+            server.publish(session.id, &session.name, snapshot);
+        }
+
+        if snapshot_changed && index == draw_context.active_session {
+            draw_context.script_console.update(session);
         }
     }
+}
+
+fn draw(ui: &mut Ui, should_exit: &mut bool, draw_context: &mut DrawContext) {
+    if draw_context.active_session >= draw_context.sessions.len() {
+        draw_context.active_session = 0;
+    }
+
+    let width = ui.io().display_size[0];
+    let height = ui.io().display_size[1];
 
     ui.main_menu_bar(|| {
         if let Some(token) = ui.begin_menu("File") {
+            if ui.menu_item("Attach") {
+                draw_context.show_attach = true;
+                draw_context.auto_attach = false;
+            };
+            if ui.menu_item("Open Dump") {
+                draw_context.open_dump_dialog.show(None);
+            };
+            if ui.menu_item("Open Recording") {
+                draw_context.open_recording_dialog.show(None);
+            };
+            if ui.menu_item("Save Dump") {
+                let path = draw_context.active_session().and_then(|session| session.snapshot.as_ref())
+                    .map(|snapshot| format!("{}_{}.bin", snapshot.map_name, snapshot.game_time_globals.local_time));
+                draw_context.save_dump_dialog.show(path);
+            };
+            if ui.menu_item("Export Snapshot") {
+                let path = draw_context.active_session().and_then(|session| session.snapshot.as_ref())
+                    .map(|snapshot| format!(
+                        "{}_{}.{}",
+                        snapshot.map_name,
+                        snapshot.game_time_globals.local_time,
+                        draw_context.export_format.extension()
+                    ));
+                draw_context.export_dialog.show(path);
+            };
+            if ui.menu_item("Export Objects CSV") {
+                let path = draw_context.active_session().and_then(|session| session.snapshot.as_ref())
+                    .map(|snapshot| format!("{}_{}.csv", snapshot.map_name, snapshot.game_time_globals.local_time));
+                draw_context.export_csv_dialog.show(path);
+            };
+            if draw_context.active_session().is_some_and(|session| session.recording.is_some()) {
+                if ui.menu_item("Stop Recording") {
+                    draw_context.sessions[draw_context.active_session].stop_recording();
+                }
+            } else if ui.menu_item("Start Recording") {
+                let mut path = None;
+                if let Some(snapshot) = draw_context.active_session().and_then(|session| session.snapshot.as_ref()) {
+                    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or_default();
+                    let name = format!("{}_{}", snapshot.map_name, timestamp);
+                    path = Some(format!("{}.ovr", name));
+                    draw_context.record_objects_csv_path = format!("{}.csv", name);
+                }
+                draw_context.record_dialog.show(path);
+            };
+            if ui.menu_item("API Server") {
+                draw_context.server_window.show();
+            };
             if ui.menu_item("Close") {
                 *should_exit = true;
             };
             if ui.menu_item("Detach") {
-                draw_context.memory = None;
+                let session = draw_context.sessions.remove(draw_context.active_session);
+                if let Some(server) = &draw_context.server {
+                    server.remove_session(session.id);
+                }
+                draw_context.active_session = draw_context.active_session.saturating_sub(1);
+                draw_context.auto_attach = false;
             };
             token.end();
         }
 
-        if let Some(snapshot) = &snapshot {
-            ui.text(" | ");
-            ui.text_colored(ORANGE, format!("Map Name: {}", snapshot.map_name));
-            ui.text_colored(ORANGE, format!("Tick: {}", snapshot.game_time_globals.local_time));
-            ui.text(" | ");
-            ui.text_colored(ORANGE, format!("Capacity: {}", snapshot.object_header.capacity));
-            ui.text_colored(ORANGE, format!("Size: {}", snapshot.object_header.size));
-            ui.text_colored(ORANGE, format!("Next Index: {} ({})", snapshot.object_header.next_index, first_free_index));
-            ui.text_colored(ORANGE, format!("Next ID: {}", snapshot.object_header.next_id));
+        if let Some(token) = ui.begin_menu("View") {
+            if ui.menu_item_config("Tabs").selected(draw_context.layout == Layout::Tabs).build() {
+                draw_context.layout = Layout::Tabs;
+            }
+            if ui.menu_item_config("Side by Side").selected(draw_context.layout == Layout::SideBySide).build() {
+                draw_context.layout = Layout::SideBySide;
+            }
+            if let Some(sample_rate_menu) = ui.begin_menu("Sample Rate") {
+                for (label, sample_rate) in SAMPLE_RATES {
+                    if ui.menu_item_config(label).selected(draw_context.sample_rate == sample_rate).build() {
+                        draw_context.sample_rate = sample_rate;
+                        for session in draw_context.sessions.iter_mut() {
+                            session.set_sample_rate(sample_rate);
+                        }
+                    }
+                }
+                sample_rate_menu.end();
+            }
+            ui.menu_item_config("Diff").build_with_ref(&mut draw_context.diff_window.open);
+            ui.menu_item_config("Events").build_with_ref(&mut draw_context.events_window.open);
+            ui.menu_item_config("Script Console").build_with_ref(&mut draw_context.script_console.open);
+            ui.separator();
+            ui.menu_item_config("Lock Scrolling")
+                .enabled(draw_context.layout == Layout::SideBySide)
+                .build_with_ref(&mut draw_context.lock_scrolling);
+            token.end();
         }
-    });    
 
-    let main_window = ui.window("Objects")
-        .size([width - 450.0, height - 20.0], Condition::Always)
-        .position([0.0, 20.0], Condition::Always)
+        if draw_context.layout == Layout::Tabs && draw_context.sessions.len() > 1 {
+            if let Some(tab_bar) = ui.tab_bar("Sessions") {
+                let selected = if std::mem::take(&mut draw_context.select_active_tab) { Some(draw_context.active_session) } else { None };
+                for (index, session) in draw_context.sessions.iter().enumerate() {
+                    let flags = if selected == Some(index) { TabItemFlags::SET_SELECTED } else { TabItemFlags::empty() };
+                    if let Some(tab) = ui.tab_item_with_flags(format!("{}###Session{}", session.name, session.id), None, flags) {
+                        draw_context.active_session = index;
+                        tab.end();
+                    }
+                }
+                tab_bar.end();
+            }
+        }
+
+        if let Some(session) = draw_context.active_session() {
+            if let Some(replay) = &session.replay {
+                let header = replay.header();
+                ui.text(" | ");
+                ui.text_colored(GREEN, format!(
+                    "Replay: {} on {} (seed {})",
+                    header.map_name,
+                    difficulty_string(header.difficulty),
+                    header.random_seed
+                ));
+            }
+
+            if let Some(snapshot) = &session.snapshot {
+                ui.text(" | ");
+                ui.text_colored(ORANGE, format!("Map Name: {}", snapshot.map_name));
+                ui.text_colored(ORANGE, format!("Tick: {}", snapshot.game_time_globals.local_time));
+                ui.text(" | ");
+                ui.text_colored(ORANGE, format!("Capacity: {}", snapshot.object_header.capacity));
+                ui.text_colored(ORANGE, format!("Size: {}", snapshot.object_header.size));
+                ui.text_colored(ORANGE, format!("Next Index: {} ({})", snapshot.object_header.next_index, snapshot.first_free_index()));
+                ui.text_colored(ORANGE, format!("Next ID: {}", snapshot.object_header.next_id));
+            }
+
+            if let Some(pages_read) = session.pages_read {
+                ui.text(" | ");
+                ui.text(format!("Pages Read: {}", pages_read));
+            }
+
+            if let Some(recording) = &session.recording {
+                ui.text(" | ");
+                ui.text_colored(RED, format!("Recording to {}", recording.display()));
+            }
+
+            if draw_context.sample_rate == SampleRate::GameTick {
+                ui.text(" | ");
+                ui.text_colored(if session.dropped_ticks > 0 { RED } else { GREEN }, format!("Dropped Ticks: {}", session.dropped_ticks));
+            }
+
+            if session.waiting_for_xemu {
+                ui.text(" | ");
+                ui.text_colored(ORANGE, "Waiting for xemu...");
+            }
+
+            if let Some(snapshot_error) = &session.snapshot_error {
+                ui.text(" | ");
+                ui.text_colored(RED, format!("Nothing to show: {}", snapshot_error));
+            }
+        }
+    });
+
+    draw_open_dump(ui, draw_context);
+    draw_open_recording(ui, draw_context);
+    draw_save_dump(ui, draw_context);
+    draw_record(ui, draw_context);
+    draw_export(ui, draw_context);
+    draw_export_csv(ui, draw_context);
+    draw_context.server_window.draw(ui, &mut draw_context.server, &draw_context.sessions);
+
+    if let Some(session) = draw_context.sessions.get_mut(draw_context.active_session) {
+        draw_context.diff_window.draw(ui, session);
+        draw_context.events_window.draw(ui, session);
+    }
+
+    for session in draw_context.sessions.iter_mut() {
+        draw_context.object_inspector.draw(ui, session);
+    }
+
+    draw_context.script_console.draw(ui, draw_context.sessions.get_mut(draw_context.active_session));
+
+    if draw_context.show_attach {
+        draw_attach_window(ui, draw_context, false);
+    }
+
+    if draw_context.sessions.is_empty() {
+        return;
+    }
+
+    match draw_context.layout {
+        Layout::Tabs => {
+            let session = &mut draw_context.sessions[draw_context.active_session];
+            draw_session(ui, session, [0.0, 20.0, width, height - 20.0], false, &mut draw_context.notifications, None);
+        }
+        Layout::SideBySide => {
+            let column_width = width / draw_context.sessions.len() as f32;
+            let shared_scroll = if draw_context.lock_scrolling { Some(&mut draw_context.shared_scroll) } else { None };
+            let mut shared_scroll = shared_scroll;
+
+            for (index, session) in draw_context.sessions.iter_mut().enumerate() {
+                let area = [column_width * index as f32, 20.0, column_width, height - 20.0];
+                if draw_session(ui, session, area, true, &mut draw_context.notifications, shared_scroll.as_deref_mut()) {
+                    draw_context.active_session = index;
+                }
+            }
+        }
+    }
+}
+
+// Draws the Objects and Players Globals windows of one session into `area` ([x, y, width, height]).
+// When `stacked` the windows are placed above each other instead of beside each other.
+// Returns true if one of the session windows has focus.
+fn draw_session(
+    ui: &Ui,
+    session: &mut Session,
+    area: [f32; 4],
+    stacked: bool,
+    notifications: &mut Notifications,
+    shared_scroll: Option<&mut Option<f32>>
+) -> bool {
+    let [x, y, width, height] = area;
+    let (objects_area, players_area) = if stacked {
+        let objects_height = (height * 0.65).floor();
+        ([x, y, width, objects_height], [x, y + objects_height, width, height - objects_height])
+    } else {
+        ([x, y, width - PLAYERS_WINDOW_WIDTH, height], [x + width - PLAYERS_WINDOW_WIDTH, y, PLAYERS_WINDOW_WIDTH, height])
+    };
+
+    let mut focused = false;
+
+    if let Some(players_window) = ui.window(format!("Players Globals - {}###Players{}", session.name, session.id))
+        .size([players_area[2], players_area[3]], Condition::Always)
+        .position([players_area[0], players_area[1]], Condition::Always)
         .resizable(false)
         .collapsible(false)
-        .begin();
+        .begin()
+    {
+        focused |= ui.is_window_focused_with_flags(WindowFocusedFlags::ROOT_AND_CHILD_WINDOWS);
 
-    let players_window = ui.window("Players Globals")
-        .size([450.0, height - 20.0], Condition::Always)
-        .position([width - 450.0, 20.0], Condition::Always)
+        if let Some(snapshot) = &session.snapshot {
+            draw_players(ui, snapshot);
+        }
+
+        players_window.end();
+    }
+
+    if let Some(main_window) = ui.window(format!("Objects - {}###Objects{}", session.name, session.id))
+        .size([objects_area[2], objects_area[3]], Condition::Always)
+        .position([objects_area[0], objects_area[1]], Condition::Always)
         .resizable(false)
         .collapsible(false)
-        .begin();
+        .begin()
+    {
+        focused |= ui.is_window_focused_with_flags(WindowFocusedFlags::ROOT_AND_CHILD_WINDOWS);
+
+        // Whichever pane the mouse is over leads, the others follow its scroll position.
+        if let Some(shared_scroll) = shared_scroll {
+            if ui.is_window_hovered() {
+                *shared_scroll = Some(ui.scroll_y());
+            } else if let Some(scroll_y) = *shared_scroll {
+                ui.set_scroll_y(scroll_y);
+            }
+        }
 
+        draw_replay_controls(ui, session, notifications);
+
+        if stacked {
+            if session.waiting_for_xemu {
+                ui.text_colored(ORANGE, "Waiting for xemu...");
+            } else if let Some(snapshot_error) = &session.snapshot_error {
+                ui.text_colored(RED, format!("Nothing to show: {}", snapshot_error));
+            } else if let Some(snapshot) = &session.snapshot {
+                ui.text_colored(ORANGE, format!(
+                    "Map Name: {} Tick: {} | Size: {} Next Index: {} ({}) Next ID: {}",
+                    snapshot.map_name,
+                    snapshot.game_time_globals.local_time,
+                    snapshot.object_header.size,
+                    snapshot.object_header.next_index,
+                    snapshot.first_free_index(),
+                    snapshot.object_header.next_id
+                ));
+            }
+        }
 
-    if let None = snapshot {
-        return;
+        draw_objects(ui, session, notifications);
+
+        main_window.end();
     }
 
-    // Snapshot should always be present after this point.
-    let snapshot = snapshot.unwrap();
+    focused
+}
 
-    if let Some(players_window) = players_window {
-        let p = &snapshot.player_globals;
-        ui.text_colored(ORANGE, format!("Respawn Failure: {}",p.respawn_failure));
-        ui.text_colored(ORANGE, format!("Are All Dead: {}", p.are_all_dead));
-        ui.text_colored(ORANGE, format!("Input Disabled: {}", p.input_disabled));
-        ui.text_colored(ORANGE, format!("Teleported: {}", p.teleported));
+fn draw_replay_controls(ui: &Ui, session: &mut Session, notifications: &mut Notifications) {
+    let Some(replay) = session.replay.as_mut() else { return; };
 
-        for player in &snapshot.player_globals.local_players {
-            if !player.is_invalid() {
-                let player_index = player.get_index();
-                ui.text(format!("-------------- Player {} --------------", player_index));
-                print_player(ui, &snapshot, player_index);
-            }
+    let last_frame = replay.frame_count() - 1;
+    let position = replay.position().unwrap_or(0);
+    let mut target = None;
+    let mut pause = true;
+
+    if ui.button("|<") {
+        target = Some(0);
+    }
+    ui.same_line();
+    if ui.button("<") {
+        target = Some(position.saturating_sub(1));
+    }
+    ui.same_line();
+    if replay.is_playing() {
+        if ui.button("Pause") {
+            replay.pause();
+        }
+    } else if ui.button("Play") {
+        if let Err(e) = replay.play() {
+            notifications.push(format!("Could not play back {}: {}", session.name, e));
         }
+    }
+    ui.same_line();
+    if ui.button(">") {
+        target = Some((position + 1).min(last_frame));
+    }
+    ui.same_line();
+    if ui.button(">|") {
+        target = Some(last_frame);
+    }
+    ui.same_line();
+
+    let mut frame = position as u32;
+    ui.set_next_item_width(-220.0);
+    // Clamped, a frame typed in with Ctrl+click could otherwise be past the end.
+    if ui.slider_config("##Timeline", 0, last_frame as u32).flags(SliderFlags::ALWAYS_CLAMP).build(&mut frame) {
+        target = Some(frame as usize);
+        pause = false;
+    }
+    ui.same_line();
+    ui.text(format!("Tick {} ({}/{})", replay.tick(frame as usize), frame + 1, last_frame + 1));
 
-        players_window.end();
+    // Stepping pauses playback, scrubbing keeps it going from the new position.
+    if let Some(target) = target {
+        if let Err(e) = session.seek_replay(target, pause) {
+            notifications.push(format!("Could not seek {}: {}", session.name, e));
+        }
     }
+}
 
-    if let Some(main_window) = main_window {
-        if let Some(table) = ui.begin_table_with_flags("ObjectsTable", 8, TableFlags::SIZING_STRETCH_PROP) {
-            ui.table_setup_column("");
-            ui.table_setup_column("Datum");
-            ui.table_setup_column("Index");
-            ui.table_setup_column("ID");
-            ui.table_setup_column("Player");
-            ui.table_setup_column("Coordinates");
-            ui.table_setup_column("Tag Name");
-            ui.table_setup_column("Object Type");
-            ui.table_headers_row();
+fn draw_players(ui: &Ui, snapshot: &EngineSnapshot) {
+    let p = &snapshot.player_globals;
+    ui.text_colored(ORANGE, format!("Respawn Failure: {}",p.respawn_failure));
+    ui.text_colored(ORANGE, format!("Are All Dead: {}", p.are_all_dead));
+    ui.text_colored(ORANGE, format!("Input Disabled: {}", p.input_disabled));
+    ui.text_colored(ORANGE, format!("Teleported: {}", p.teleported));
+
+    for player in &snapshot.player_globals.local_players {
+        if !player.is_invalid() {
+            let player_index = player.get_index();
+            ui.text(format!("-------------- Player {} --------------", player_index));
+            print_player(ui, snapshot, player_index);
+        }
+    }
+}
 
-            for index in (0..snapshot.object_header.max_entries as usize).rev() {
-                ui.table_next_row();
+fn draw_objects(ui: &Ui, session: &mut Session, notifications: &mut Notifications) {
+    let Some(snapshot) = session.snapshot.take() else { return; };
+    let first_free_index = snapshot.first_free_index();
+
+    if let Some(table) = ui.begin_table_with_flags("ObjectsTable", 8, TableFlags::SIZING_STRETCH_PROP) {
+        ui.table_setup_column("");
+        ui.table_setup_column("Datum");
+        ui.table_setup_column("Index");
+        ui.table_setup_column("ID");
+        ui.table_setup_column("Player");
+        ui.table_setup_column("Coordinates");
+        ui.table_setup_column("Tag Name");
+        ui.table_setup_column("Object Type");
+        ui.table_headers_row();
+
+        for index in (0..snapshot.object_header.max_entries as usize).rev() {
+            ui.table_next_row();
+
+            let is_row_valid = snapshot.object_entries[index].is_some();
+            let identity = ui.push_id_usize(index);
+
+            if is_row_valid {
+                let object_pool_entry = snapshot.object_header_entries[index].as_ref().unwrap();
+                let game_object_entry = snapshot.object_entries[index].as_ref().unwrap();
 
-                let is_row_valid = snapshot.object_entries[index].is_some();
-                let identity = ui.push_id_usize(index);
+                let datum_handle = Datum::from_parts(index as u16, object_pool_entry.id);
 
-                if is_row_valid {
-                    let object_pool_entry = snapshot.object_header_entries[index].as_ref().unwrap();
-                    let game_object_entry = snapshot.object_entries[index].as_ref().unwrap();
-    
-                    let datum_handle = Datum::from_parts(index as u16, object_pool_entry.id);
-    
-                    ui.table_set_column_index(0);
-    
-                    if ui.button(&"Set") {
-                        draw_context.target_index = index as u32;
-                    }
-                    
-                    if index == draw_context.target_index as usize {
-                        ui.table_set_bg_color(TableBgTarget::ROW_BG0, DARK_GREY);
-                    }
-    
-                    ui.table_next_column();
-                    ui.text_colored(if first_free_index as usize == index { ORANGE } else { GREEN }, format!("{}", datum_handle.get_handle()));
-    
-                    ui.table_next_column();
-                    ui.text_colored(if first_free_index as usize == index { ORANGE } else { GREEN }, format!("{}", index));
-    
-                    ui.table_next_column();
-                    ui.text_colored(
-                        if object_pool_entry.id == snapshot.object_header.next_id { ORANGE } else { WHITE }, 
-                        format!("{:<5}", object_pool_entry.id )
-                    );
-    
-                    ui.table_next_column();
-                    if let Some(player_index) = snapshot.find_local_player_index_from_unit_index(index as u16) {
-                        ui.text_colored(GREEN, format!("{}", player_index))
-                    } else if let Some(local_dead_player_index) = snapshot.find_next_object_datum_player(datum_handle.clone()) {
-                        ui.text_colored(RED, format!("{}", local_dead_player_index));
-                    } else {
-                        ui.text("");
-                    }
-    
-                    ui.table_next_column();
-                    let mut updated_position = game_object_entry.position.clone();
-    
-                    if ui.input_float3(&"POS", &mut updated_position).build() {
-                        let manager = draw_context.memory.as_mut().unwrap();
-                        let game_object_pointer = Memory::fix_pointer(object_pool_entry.object_address);
-    
-                        manager.write(game_object_pointer + 0xC, &updated_position.get(0).as_ref().expect("Could not write X position").to_le_bytes());
-                        manager.write(game_object_pointer + 0xC + 0x4, &updated_position.get(1).as_ref().expect("Could not write Y position").to_le_bytes());
-                        manager.write(game_object_pointer + 0xC + 0x4 + 0x4, &updated_position.get(2).as_ref().expect("Could not write Z position").to_le_bytes());
-                    }
-    
-                    ui.table_next_column();
-                    ui.text(snapshot.tags.get(&game_object_entry.tag_index).unwrap_or(&"UNKNOWN".to_string()).split("\\").last().unwrap());
-    
-                    ui.table_next_column();        
-                    ui.text(object_type_string(object_pool_entry.data_type));
-                } else {
-                    ui.table_set_column_index(0);
+                ui.table_set_column_index(0);
+                let column_start = ui.cursor_pos()[0];
 
-                    if ui.button(&"Set") {
-                        draw_context.target_index = index as u32;
-                    }
+                // Clicking anywhere else on the row opens the inspector for it.
+                if ui.selectable_config("##Inspect").flags(SelectableFlags::SPAN_ALL_COLUMNS | SelectableFlags::ALLOW_ITEM_OVERLAP).build() {
+                    session.target_index = index as u32;
+                    session.inspecting = true;
+                }
+                ui.same_line_with_pos(column_start);
 
-                    if index == draw_context.target_index as usize {
-                        ui.table_set_bg_color(TableBgTarget::ROW_BG0, DARK_GREY);
-                    }
+                if ui.button("Set") {
+                    session.target_index = index as u32;
+                }
 
-                    ui.table_next_column();
-                    ui.text("");
+                if index == session.target_index as usize {
+                    ui.table_set_bg_color(TableBgTarget::ROW_BG0, DARK_GREY);
+                }
 
-                    ui.table_next_column();
-                    ui.text_colored(if first_free_index as usize == index { ORANGE } else { RED }, format!("{}", index));
+                ui.table_next_column();
+                ui.text_colored(if first_free_index == index { ORANGE } else { GREEN }, format!("{}", datum_handle.get_handle()));
 
-                    ui.table_next_column();
-                    ui.text("Free");
+                ui.table_next_column();
+                ui.text_colored(if first_free_index == index { ORANGE } else { GREEN }, format!("{}", index));
 
-                    ui.table_next_column();
-                    ui.text("");
+                ui.table_next_column();
+                ui.text_colored(
+                    if object_pool_entry.id == snapshot.object_header.next_id { ORANGE } else { WHITE }, 
+                    format!("{:<5}", object_pool_entry.id )
+                );
 
-                    ui.table_next_column();
+                ui.table_next_column();
+                if let Some(player_index) = snapshot.find_local_player_index_from_unit_index(index as u16) {
+                    ui.text_colored(GREEN, format!("{}", player_index))
+                } else if let Some(local_dead_player_index) = snapshot.find_next_object_datum_player(datum_handle.clone()) {
+                    ui.text_colored(RED, format!("{}", local_dead_player_index));
+                } else {
                     ui.text("");
+                }
 
-                    ui.table_next_column();
-                    ui.text("");
+                ui.table_next_column();
+                let mut updated_position = game_object_entry.position;
 
-                    ui.table_next_column();
-                    ui.text("");
+                if ui.input_float3(&"POS", &mut updated_position).build() {
+                    let game_object_pointer = Memory::fix_pointer(object_pool_entry.object_address);
+                    let position_bytes: Vec<u8> = updated_position.iter().flat_map(|axis| axis.to_le_bytes()).collect();
+
+                    if let Err(e) = session.write_memory(game_object_pointer + 0xC, &position_bytes) {
+                        notifications.push(format!("Could not write position of object {} in {}: {}", index, session.name, e));
+                    }
+                }
+
+                ui.table_next_column();
+                ui.text(snapshot.tags.get(&game_object_entry.tag_index).unwrap_or(&"UNKNOWN".to_string()).split("\\").last().unwrap());
+
+                ui.table_next_column();        
+                ui.text(object_type_string(object_pool_entry.data_type));
+            } else {
+                ui.table_set_column_index(0);
+                let column_start = ui.cursor_pos()[0];
+
+                if ui.selectable_config("##Inspect").flags(SelectableFlags::SPAN_ALL_COLUMNS | SelectableFlags::ALLOW_ITEM_OVERLAP).build() {
+                    session.target_index = index as u32;
+                    session.inspecting = true;
+                }
+                ui.same_line_with_pos(column_start);
+
+                if ui.button("Set") {
+                    session.target_index = index as u32;
+                }
+
+                if index == session.target_index as usize {
+                    ui.table_set_bg_color(TableBgTarget::ROW_BG0, DARK_GREY);
                 }
 
-                identity.end();
+                ui.table_next_column();
+                ui.text("");
+
+                ui.table_next_column();
+                ui.text_colored(if first_free_index == index { ORANGE } else { RED }, format!("{}", index));
+
+                ui.table_next_column();
+                ui.text("Free");
+
+                ui.table_next_column();
+                ui.text("");
+
+                ui.table_next_column();
+                ui.text("");
+
+                ui.table_next_column();
+                ui.text("");
+
+                ui.table_next_column();
+                ui.text("");
             }
 
-            table.end();
+            identity.end();
         }
 
-        main_window.end();
+        table.end();
     }
+
+    session.snapshot = Some(snapshot);
 }
 
+// How long ago a process started, given its start time in seconds since the unix epoch.
+fn format_age(start_time: u64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or_default();
+    let age = now.saturating_sub(start_time);
+
+    format!("{}h {:02}m {:02}s ago", age / 3600, (age / 60) % 60, age % 60)
+}
 
 fn draw_attach(ui: &mut Ui, should_exit: &mut bool, draw_context: &mut DrawContext) {
     ui.main_menu_bar(|| {
         if let Some(token) = ui.begin_menu("File") {
+            if ui.menu_item("Open Dump") {
+                draw_context.open_dump_dialog.show(None);
+            };
+            if ui.menu_item("Open Recording") {
+                draw_context.open_recording_dialog.show(None);
+            };
             if ui.menu_item("Close") {
                 *should_exit = true;
             };
@@ -296,62 +874,175 @@ fn draw_attach(ui: &mut Ui, should_exit: &mut bool, draw_context: &mut DrawConte
         }
     });
 
+    draw_open_dump(ui, draw_context);
+    draw_open_recording(ui, draw_context);
+    draw_attach_window(ui, draw_context, true);
+}
+
+// Lists the running emulators to attach to, covering the whole screen while nothing is attached yet.
+fn draw_attach_window(ui: &Ui, draw_context: &mut DrawContext, fullscreen: bool) {
     let width = ui.io().display_size[0];
     let height = ui.io().display_size[1];
 
-    let attach_window = ui.window("Attach")
-        .size([width, height - 20.0], Condition::Always)
-        .position([0.0, 20.0], Condition::Always)
-        .resizable(false)
-        .collapsible(false)
-        .begin();
-
+    let mut opened = true;
+    let attach_window = if fullscreen {
+        ui.window("Attach")
+            .size([width, height - 20.0], Condition::Always)
+            .position([0.0, 20.0], Condition::Always)
+            .resizable(false)
+            .collapsible(false)
+            .begin()
+    } else {
+        ui.window("Attach")
+            .opened(&mut opened)
+            .size([900.0, 400.0], Condition::FirstUseEver)
+            .position([100.0, 80.0], Condition::FirstUseEver)
+            .collapsible(false)
+            .begin()
+    };
 
     if let Some(attach_window) = attach_window {
+        let attached_pids = draw_context.attached_pids();
+        let processes = find_xemu_processes();
+        let unattached: Vec<u32> = processes.iter()
+            .map(|process| process.pid)
+            .filter(|pid| !attached_pids.contains(pid))
+            .collect();
+
+        // Forget a selection once that xemu is gone, and pick the only instance when there is no choice to make.
+        if !draw_context.selected_pid.is_some_and(|pid| unattached.contains(&pid)) {
+            draw_context.selected_pid = None;
+        }
 
-        let sys = System::new_all();
-        let processes: Vec<_> = sys.processes_by_exact_name(OsStr::new("xemu.exe")).collect();
+        if unattached.len() == 1 && draw_context.selected_pid.is_none() && draw_context.auto_attach {
+            draw_context.selected_pid = Some(unattached[0]);
+        }
 
-        if processes.len() == 0 {
-            ui.text("Could not find running instance of xemu.exe");
-        } else if processes.len() > 1 {
-            ui.text("Found multiple instances of xemu.exe running on the system. Please only have one instance running.");
-        } else if processes.len() == 1 {
-            ui.text("Found xemu.exe");
+        if processes.is_empty() {
+            ui.text("Could not find running instance of xemu");
+        } else {
+            ui.text(format!("Found {} instance(s) of xemu", processes.len()));
 
-            let process = processes[0];
+            if let Some(table) = ui.begin_table_with_flags("ProcessTable", 5, TableFlags::SIZING_STRETCH_PROP | TableFlags::ROW_BG) {
+                ui.table_setup_column("");
+                ui.table_setup_column("PID");
+                ui.table_setup_column("Started");
+                ui.table_setup_column("Window Title");
+                ui.table_setup_column("Command Line");
+                ui.table_headers_row();
 
-            ui.text(r#"Run (gpa2hva 0x0) in xemu.exe and put the result below."#);
-            
-            ui.input_text("Virtual Address to Physical Xbox Memory", &mut draw_context.virtual_address)
-                .allow_tab_input(false)
-                .chars_hexadecimal(true)
-                .chars_noblank(true)
-                .build();
+                for process in &processes {
+                    ui.table_next_row();
+                    let identity = ui.push_id_usize(process.pid as usize);
+
+                    ui.table_set_column_index(0);
+                    if attached_pids.contains(&process.pid) {
+                        ui.text_colored(GREEN, "Attached");
+                    } else if ui.button("Attach") {
+                        draw_context.selected_pid = Some(process.pid);
+                        draw_context.auto_attach = true;
+                        draw_context.last_attach_attempt = None;
+                    }
+
+                    if draw_context.selected_pid == Some(process.pid) {
+                        ui.table_set_bg_color(TableBgTarget::ROW_BG0, DARK_GREY);
+                    }
+
+                    ui.table_next_column();
+                    ui.text(format!("{}", process.pid));
+
+                    ui.table_next_column();
+                    ui.text(format_age(process.start_time));
 
-            if ui.button("Set Virtual Address") {
-                if let Ok(value) = usize::from_str_radix(&draw_context.virtual_address, 16) {
-                    let mem = Memory::new(value, 67108864, process.pid().as_u32());
-                    draw_context.memory = Some(
-                        mem
-                    );
+                    ui.table_next_column();
+                    ui.text(process.window_title.as_deref().unwrap_or(""));
+
+                    ui.table_next_column();
+                    ui.text(&process.command_line);
+
+                    identity.end();
                 }
+
+                table.end();
             }
 
-    
+            ui.separator();
+
+            if let Some(pid) = draw_context.selected_pid {
+                if draw_context.auto_attach {
+                    ui.text(format!("Searching xemu ({}) for the Halo guest memory, waiting for the game to boot...", pid));
+                    draw_context.try_attach(pid);
+                }
+
+                ui.separator();
+                ui.text(r#"If the guest memory is not found, run (gpa2hva 0x0) in xemu and put the result below."#);
+
+                ui.input_text("Virtual Address to Physical Xbox Memory", &mut draw_context.virtual_address)
+                    .allow_tab_input(false)
+                    .chars_hexadecimal(true)
+                    .chars_noblank(true)
+                    .build();
+
+                if ui.button("Set Virtual Address") {
+                    if let Ok(value) = usize::from_str_radix(&draw_context.virtual_address, 16) {
+                        match ProcessSource::new(value, XBOX_MEMORY_SIZE, pid) {
+                            Ok(source) => {
+                                draw_context.add_session(format!("xemu {}", pid), Memory::new(source), Some(pid));
+                                draw_context.selected_pid = None;
+                                draw_context.show_attach = false;
+                            }
+                            Err(e) => draw_context.notifications.push(format!("Could not open xemu ({}): {}", pid, e))
+                        }
+                    }
+                }
+            } else if unattached.is_empty() {
+                ui.text("Every running instance of xemu is already attached.");
+            } else {
+                ui.text("Pick the instance of xemu to attach to.");
+            }
         }
 
         attach_window.end();
     }
 
+    if !opened {
+        draw_context.show_attach = false;
+    }
 }
 
 fn start() {
     // Setup draw context
     let mut draw_context = DrawContext {
+        sessions: Vec::new(),
+        active_session: 0,
+        select_active_tab: false,
+        next_session_id: 0,
+        layout: Layout::Tabs,
+        sample_rate: SampleRate::GameTick,
+        lock_scrolling: false,
+        shared_scroll: None,
+        show_attach: false,
         virtual_address: String::default(),
-        memory: None,
-        target_index: u32::MAX
+        auto_attach: true,
+        selected_pid: None,
+        last_attach_attempt: None,
+        open_dump_dialog: PathDialog::new("Open Dump", "Path to RAM dump", "Open"),
+        save_dump_dialog: PathDialog::new("Save Dump", "Path to write RAM dump", "Save"),
+        record_dialog: PathDialog::new("Record", "Path to write recording", "Record"),
+        record_objects_csv: false,
+        record_objects_csv_path: String::default(),
+        open_recording_dialog: PathDialog::new("Open Recording", "Path to recording", "Open"),
+        export_dialog: PathDialog::new("Export Snapshot", "Path to write snapshot", "Export"),
+        export_format: ExportFormat::Json,
+        export_csv_dialog: PathDialog::new("Export Objects CSV", "Path to write CSV", "Export"),
+        export_csv_include_free: false,
+        server: None,
+        server_window: ServerWindow::default(),
+        diff_window: DiffWindow::default(),
+        events_window: EventsWindow::default(),
+        script_console: ScriptConsole::default(),
+        object_inspector: ObjectInspector::default(),
+        notifications: Notifications::default()
     };
 
     /* */
@@ -421,12 +1112,16 @@ fn start() {
         
         /* create imgui UI here */
         
-        if draw_context.memory.is_some() {
+        update_sessions(&mut draw_context);
+
+        if !draw_context.sessions.is_empty() {
             draw(ui, &mut should_exit, &mut draw_context);
         } else {
             draw_attach(ui, &mut should_exit, &mut draw_context);
         }
 
+        draw_context.notifications.draw(ui);
+
         if should_exit {
             break 'main;
         }
@@ -442,5 +1137,11 @@ fn start() {
 }
 
 fn main() {
-    start();
+    // Any arguments select a headless command, see `objectviewer help`.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        start();
+    } else {
+        std::process::exit(cli::run(&args));
+    }
 }
@@ -0,0 +1,36 @@
+use std::io;
+
+use super::MemorySource;
+
+// Guest RAM held entirely in our own address space, e.g. a synthetic test image.
+pub struct BufferSource {
+    bytes: Vec<u8>
+}
+
+impl BufferSource {
+    pub fn new(bytes: Vec<u8>) -> BufferSource {
+        BufferSource { bytes }
+    }
+
+    pub fn zeroed(capacity: usize) -> BufferSource {
+        BufferSource::new(vec![0; capacity])
+    }
+}
+
+impl MemorySource for BufferSource {
+    fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn read_range(&self, physical_address: u32, buffer: &mut [u8]) -> io::Result<()> {
+        let start = physical_address as usize;
+        buffer.copy_from_slice(&self.bytes[start..start + buffer.len()]);
+        Ok(())
+    }
+
+    fn write_range(&mut self, physical_address: u32, bytes: &[u8]) -> io::Result<()> {
+        let start = physical_address as usize;
+        self.bytes[start..start + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+use std::{collections::HashMap, io};
+
+use super::MemorySource;
+
+// Granularity of the cache, matches the page size of both the Xbox and the host.
+pub const PAGE_SIZE: usize = 4096;
+
+struct Page {
+    generation: u64,
+    bytes: Box<[u8]>
+}
+
+// Pages of a remote source read since the last refresh, so a snapshot only pulls in the memory it looks at.
+#[derive(Default)]
+pub struct PageCache {
+    pages: HashMap<u32, Page>,
+    generation: u64,
+    pages_read: usize
+}
+
+impl PageCache {
+    // Mark every cached page stale, the allocations are kept and reused by the next reads.
+    pub fn invalidate(&mut self) {
+        self.generation += 1;
+        self.pages_read = 0;
+    }
+
+    // Drop the pages overlapping a range we just wrote to.
+    pub fn invalidate_range(&mut self, physical_address: u32, length: usize) {
+        for page in PageCache::page_range(physical_address, length) {
+            self.pages.remove(&page);
+        }
+    }
+
+    // Number of pages fetched from the source since the last invalidate.
+    pub fn pages_read(&self) -> usize {
+        self.pages_read
+    }
+
+    // Every page read since the last invalidate, in address order.
+    pub fn fresh_pages(&self, mut visit: impl FnMut(u32, &[u8])) {
+        let mut pages: Vec<u32> = self.pages.keys().copied().filter(|page| self.is_fresh(*page)).collect();
+        pages.sort_unstable();
+
+        for page in pages {
+            visit(page, &self.pages[&page].bytes);
+        }
+    }
+
+    fn page_range(physical_address: u32, length: usize) -> std::ops::Range<u32> {
+        let first = physical_address / PAGE_SIZE as u32;
+        let last = (physical_address as usize + length.max(1) - 1) / PAGE_SIZE;
+        first..last as u32 + 1
+    }
+
+    fn is_fresh(&self, page: u32) -> bool {
+        self.pages.get(&page).is_some_and(|cached| cached.generation == self.generation)
+    }
+
+    // Callers guarantee the range lies within source.size(), which is a whole number of pages.
+    pub fn read(&mut self, source: &dyn MemorySource, physical_address: u32, buffer: &mut [u8]) -> io::Result<()> {
+        let pages = PageCache::page_range(physical_address, buffer.len());
+
+        // Fetch each run of stale pages with a single read instead of one per page.
+        let mut page = pages.start;
+        while page < pages.end {
+            if self.is_fresh(page) {
+                page += 1;
+                continue;
+            }
+
+            let run_start = page;
+            while page < pages.end && !self.is_fresh(page) {
+                page += 1;
+            }
+
+            let mut run = vec![0; (page - run_start) as usize * PAGE_SIZE];
+            source.read_range(run_start * PAGE_SIZE as u32, &mut run)?;
+
+            for (offset, bytes) in run.chunks_exact(PAGE_SIZE).enumerate() {
+                let cached = self.pages.entry(run_start + offset as u32).or_insert_with(|| Page {
+                    generation: 0,
+                    bytes: vec![0; PAGE_SIZE].into_boxed_slice()
+                });
+
+                cached.bytes.copy_from_slice(bytes);
+                cached.generation = self.generation;
+            }
+
+            self.pages_read += (page - run_start) as usize;
+        }
+
+        let mut copied = 0;
+        for page in pages {
+            let page_start = page as usize * PAGE_SIZE;
+            let start = (physical_address as usize + copied) - page_start;
+            let length = (PAGE_SIZE - start).min(buffer.len() - copied);
+
+            buffer[copied..copied + length].copy_from_slice(&self.pages[&page].bytes[start..start + length]);
+            copied += length;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    const PAGES: usize = 4;
+
+    // Buffer that remembers the ranges read from it.
+    struct CountingSource {
+        bytes: Vec<u8>,
+        reads: RefCell<Vec<(u32, usize)>>
+    }
+
+    impl CountingSource {
+        fn new() -> CountingSource {
+            CountingSource {
+                bytes: (0..PAGES * PAGE_SIZE).map(|i| (i % 251) as u8).collect(),
+                reads: RefCell::new(Vec::new())
+            }
+        }
+
+        fn take_reads(&self) -> Vec<(u32, usize)> {
+            std::mem::take(&mut self.reads.borrow_mut())
+        }
+    }
+
+    impl MemorySource for CountingSource {
+        fn size(&self) -> usize {
+            self.bytes.len()
+        }
+
+        fn read_range(&self, physical_address: u32, buffer: &mut [u8]) -> io::Result<()> {
+            self.reads.borrow_mut().push((physical_address, buffer.len()));
+            let start = physical_address as usize;
+            buffer.copy_from_slice(&self.bytes[start..start + buffer.len()]);
+            Ok(())
+        }
+
+        fn write_range(&mut self, physical_address: u32, bytes: &[u8]) -> io::Result<()> {
+            let start = physical_address as usize;
+            self.bytes[start..start + bytes.len()].copy_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    fn read(cache: &mut PageCache, source: &CountingSource, physical_address: u32, length: usize) -> Vec<u8> {
+        let mut buffer = vec![0; length];
+        cache.read(source, physical_address, &mut buffer).unwrap();
+        buffer
+    }
+
+    fn fresh_pages(cache: &PageCache) -> Vec<(u32, Vec<u8>)> {
+        let mut pages = Vec::new();
+        cache.fresh_pages(|page, bytes| pages.push((page, bytes.to_vec())));
+        pages
+    }
+
+    #[test]
+    fn read_across_pages_fetches_them_in_one_go() {
+        let source = CountingSource::new();
+        let mut cache = PageCache::default();
+
+        let address = PAGE_SIZE as u32 - 8;
+        assert_eq!(read(&mut cache, &source, address, 16), source.bytes[PAGE_SIZE - 8..PAGE_SIZE + 8]);
+        assert_eq!(source.take_reads(), [(0, 2 * PAGE_SIZE)]);
+        assert_eq!(cache.pages_read(), 2);
+    }
+
+    #[test]
+    fn cached_pages_are_not_fetched_again() {
+        let source = CountingSource::new();
+        let mut cache = PageCache::default();
+
+        read(&mut cache, &source, PAGE_SIZE as u32, 4);
+        source.take_reads();
+
+        // Only the stale runs either side of the cached page are fetched.
+        assert_eq!(read(&mut cache, &source, 0, PAGES * PAGE_SIZE), source.bytes);
+        assert_eq!(source.take_reads(), [(0, PAGE_SIZE), (2 * PAGE_SIZE as u32, 2 * PAGE_SIZE)]);
+        assert_eq!(cache.pages_read(), PAGES);
+
+        read(&mut cache, &source, 0, PAGES * PAGE_SIZE);
+        assert!(source.take_reads().is_empty());
+    }
+
+    #[test]
+    fn invalidate_refetches_and_leaves_only_new_reads_fresh() {
+        let mut source = CountingSource::new();
+        let mut cache = PageCache::default();
+
+        read(&mut cache, &source, 0, 4);
+        read(&mut cache, &source, 2 * PAGE_SIZE as u32, 4);
+        cache.invalidate();
+        assert!(fresh_pages(&cache).is_empty());
+        assert_eq!(cache.pages_read(), 0);
+
+        source.write_range(2 * PAGE_SIZE as u32, &[0xAA; 4]).unwrap();
+        source.take_reads();
+
+        assert_eq!(read(&mut cache, &source, 2 * PAGE_SIZE as u32, 4), [0xAA; 4]);
+        assert_eq!(source.take_reads(), [(2 * PAGE_SIZE as u32, PAGE_SIZE)]);
+        assert_eq!(fresh_pages(&cache), [(2, source.bytes[2 * PAGE_SIZE..3 * PAGE_SIZE].to_vec())]);
+        assert_eq!(cache.pages_read(), 1);
+    }
+
+    #[test]
+    fn fresh_pages_are_visited_in_address_order() {
+        let source = CountingSource::new();
+        let mut cache = PageCache::default();
+
+        read(&mut cache, &source, 3 * PAGE_SIZE as u32, 4);
+        read(&mut cache, &source, PAGE_SIZE as u32, 4);
+
+        let pages: Vec<u32> = fresh_pages(&cache).into_iter().map(|(page, _)| page).collect();
+        assert_eq!(pages, [1, 3]);
+    }
+
+    #[test]
+    fn invalidate_range_refetches_only_the_written_pages() {
+        let mut source = CountingSource::new();
+        let mut cache = PageCache::default();
+
+        read(&mut cache, &source, 0, 2 * PAGE_SIZE);
+        source.write_range(PAGE_SIZE as u32 + 4, &[0xAA; 4]).unwrap();
+        cache.invalidate_range(PAGE_SIZE as u32 + 4, 4);
+        source.take_reads();
+
+        assert_eq!(read(&mut cache, &source, 0, 2 * PAGE_SIZE), source.bytes[..2 * PAGE_SIZE]);
+        assert_eq!(source.take_reads(), [(PAGE_SIZE as u32, PAGE_SIZE)]);
+    }
+}
@@ -0,0 +1,127 @@
+use std::{fs::{self, File}, io, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use memmap2::{MmapMut, MmapOptions};
+
+use super::{Memory, MemorySource, XBOX_MEMORY_SIZE};
+
+// Describes the game state captured in a dump, stored next to it as `<dump>.meta`.
+#[derive(Debug)]
+pub struct DumpMetadata {
+    pub map_name: String,
+    pub local_time: u32,
+    pub virtual_address: Option<usize>,
+    pub timestamp: u64
+}
+
+impl DumpMetadata {
+    fn sidecar_path(dump_path: impl AsRef<Path>) -> PathBuf {
+        let mut path = dump_path.as_ref().as_os_str().to_owned();
+        path.push(".meta");
+        PathBuf::from(path)
+    }
+
+    fn save(&self, dump_path: impl AsRef<Path>) -> io::Result<()> {
+        let virtual_address = self.virtual_address.map(|address| format!("{:x}", address)).unwrap_or_default();
+        let contents = format!(
+            "map_name={}\nlocal_time={}\nvirtual_address={}\ntimestamp={}\n",
+            self.map_name, self.local_time, virtual_address, self.timestamp
+        );
+
+        fs::write(DumpMetadata::sidecar_path(dump_path), contents)
+    }
+}
+
+// Write the full guest RAM behind `memory` to `path` along with its metadata sidecar.
+pub fn save_dump(memory: &Memory, path: impl AsRef<Path>, map_name: &str, local_time: u32) -> io::Result<()> {
+    fs::write(path.as_ref(), memory.bytes().map_err(io::Error::other)?)?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+
+    let metadata = DumpMetadata {
+        map_name: map_name.to_string(),
+        local_time,
+        virtual_address: memory.virtual_address(),
+        timestamp
+    };
+
+    metadata.save(path)
+}
+
+enum DumpStorage {
+    // Private copy-on-write mapping, edits never reach the file on disk.
+    Mapped(MmapMut),
+    Loaded(Vec<u8>)
+}
+
+// A raw guest-physical RAM image captured from a previous session.
+pub struct DumpSource {
+    storage: DumpStorage
+}
+
+impl DumpSource {
+    // Map the dump into our address space, pages are only read from disk when touched.
+    // Falls back to reading the whole dump on file systems that do not support mapping.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<DumpSource> {
+        let file = File::open(path.as_ref())?;
+        DumpSource::check_length(file.metadata()?.len() as usize)?;
+
+        // The mapping is private, so the only hazard is another process truncating the dump while we view it.
+        match unsafe { MmapOptions::new().map_copy(&file) } {
+            Ok(mapping) => Ok(DumpSource { storage: DumpStorage::Mapped(mapping) }),
+            Err(_) => DumpSource::load(path)
+        }
+    }
+
+    fn load(path: impl AsRef<Path>) -> io::Result<DumpSource> {
+        let bytes = fs::read(path.as_ref())?;
+        DumpSource::check_length(bytes.len())?;
+
+        Ok(DumpSource { storage: DumpStorage::Loaded(bytes) })
+    }
+
+    fn check_length(length: usize) -> io::Result<()> {
+        if length < XBOX_MEMORY_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Dump is {} bytes, expected at least {} bytes of guest RAM", length, XBOX_MEMORY_SIZE)
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match &self.storage {
+            DumpStorage::Mapped(mapping) => mapping,
+            DumpStorage::Loaded(bytes) => bytes
+        }
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        match &mut self.storage {
+            DumpStorage::Mapped(mapping) => mapping,
+            DumpStorage::Loaded(bytes) => bytes
+        }
+    }
+}
+
+impl MemorySource for DumpSource {
+    fn size(&self) -> usize {
+        self.bytes().len()
+    }
+
+    fn read_range(&self, physical_address: u32, buffer: &mut [u8]) -> io::Result<()> {
+        let start = physical_address as usize;
+        buffer.copy_from_slice(&self.bytes()[start..start + buffer.len()]);
+        Ok(())
+    }
+
+    fn write_range(&mut self, physical_address: u32, bytes: &[u8]) -> io::Result<()> {
+        let start = physical_address as usize;
+        self.bytes_mut()[start..start + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+}
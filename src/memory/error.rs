@@ -0,0 +1,32 @@
+use std::{error::Error, fmt};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemoryError {
+    OutOfRange { address: u32, length: usize },
+    Misaligned { address: u32, alignment: usize },
+    UnterminatedString { address: u32, max_length: usize },
+    InvalidUtf8 { address: u32 },
+    ReadFailed { address: u32, length: usize, reason: String },
+    WriteFailed { address: u32, length: usize, reason: String }
+}
+
+impl fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryError::OutOfRange { address, length } =>
+                write!(f, "access of {} bytes at {:#010x} is outside guest memory", length, address),
+            MemoryError::Misaligned { address, alignment } =>
+                write!(f, "address {:#010x} is not aligned to {} bytes", address, alignment),
+            MemoryError::UnterminatedString { address, max_length } =>
+                write!(f, "string at {:#010x} is not terminated within {} bytes", address, max_length),
+            MemoryError::InvalidUtf8 { address } =>
+                write!(f, "string at {:#010x} is not valid UTF-8", address),
+            MemoryError::ReadFailed { address, length, reason } =>
+                write!(f, "read of {} bytes at {:#010x} failed: {}", length, address, reason),
+            MemoryError::WriteFailed { address, length, reason } =>
+                write!(f, "write of {} bytes at {:#010x} failed: {}", length, address, reason)
+        }
+    }
+}
+
+impl Error for MemoryError {}
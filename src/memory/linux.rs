@@ -0,0 +1,124 @@
+use std::{ffi::c_void, fs::{self, File}, io, os::unix::fs::FileExt};
+
+use super::MemorySource;
+
+// Reads the guest RAM of a running xemu through process_vm_readv, falling back to /proc/<pid>/mem
+// when the kernel or ptrace policy does not allow cross memory attach.
+pub struct ProcessSource {
+    size: usize,
+    virtual_address: usize,
+    pid: u32,
+    start_time: Option<u64>,
+    mem_file: Option<File>
+}
+
+impl ProcessSource {
+    // Never fails, process_vm_readv needs no handle and /proc/<pid>/mem is only a fallback.
+    pub fn new(virtual_address: usize, capacity: usize, pid: u32) -> io::Result<ProcessSource> {
+        let mem_file = File::options()
+            .read(true)
+            .write(true)
+            .open(format!("/proc/{}/mem", pid))
+            .ok();
+
+        Ok(ProcessSource {
+            size: capacity,
+            pid,
+            virtual_address,
+            start_time: ProcessSource::start_time(pid),
+            mem_file
+        })
+    }
+
+    // Start time of the process in clock ticks since boot, used to tell a restarted xemu apart when its pid is reused.
+    fn start_time(pid: u32) -> Option<u64> {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+
+        // The command name may contain spaces, so count fields from its closing parenthesis.
+        let (_, fields) = stat.rsplit_once(')')?;
+        fields.split_whitespace().nth(19)?.parse().ok()
+    }
+
+    // Base addresses of every anonymous read-write mapping in the process that is exactly `size` bytes.
+    pub fn find_regions(pid: u32, size: usize) -> Vec<usize> {
+        let Ok(maps) = fs::read_to_string(format!("/proc/{}/maps", pid)) else { return Vec::new(); };
+
+        let mut regions = Vec::new();
+        for line in maps.lines() {
+            // start-end perms offset dev inode [pathname]
+            let mut fields = line.split_whitespace();
+            let (Some(range), Some(perms)) = (fields.next(), fields.next()) else { continue; };
+            let pathname = fields.nth(3).unwrap_or("");
+
+            // Guest RAM is either a plain anonymous mapping or a memfd when xemu shares it.
+            let is_anonymous = pathname.is_empty() || pathname.starts_with("/memfd:") || pathname.starts_with("[anon");
+            if !perms.starts_with("rw") || !is_anonymous { continue; }
+
+            let Some((start, end)) = range.split_once('-') else { continue; };
+            let (Ok(start), Ok(end)) = (usize::from_str_radix(start, 16), usize::from_str_radix(end, 16)) else { continue; };
+
+            if end - start == size {
+                regions.push(start);
+            }
+        }
+
+        regions
+    }
+
+    fn read_remote(&self, remote_address: usize, buffer: &mut [u8]) -> io::Result<()> {
+        let local = libc::iovec { iov_base: buffer.as_mut_ptr() as *mut c_void, iov_len: buffer.len() };
+        let remote = libc::iovec { iov_base: remote_address as *mut c_void, iov_len: buffer.len() };
+
+        let read = unsafe { libc::process_vm_readv(self.pid as libc::pid_t, &local, 1, &remote, 1, 0) };
+        if read == buffer.len() as isize {
+            return Ok(());
+        }
+
+        match &self.mem_file {
+            Some(mem_file) => mem_file.read_exact_at(buffer, remote_address as u64),
+            None => Err(io::Error::last_os_error())
+        }
+    }
+
+    fn write_remote(&self, remote_address: usize, bytes: &[u8]) -> io::Result<()> {
+        let local = libc::iovec { iov_base: bytes.as_ptr() as *mut c_void, iov_len: bytes.len() };
+        let remote = libc::iovec { iov_base: remote_address as *mut c_void, iov_len: bytes.len() };
+
+        let written = unsafe { libc::process_vm_writev(self.pid as libc::pid_t, &local, 1, &remote, 1, 0) };
+        if written == bytes.len() as isize {
+            return Ok(());
+        }
+
+        match &self.mem_file {
+            Some(mem_file) => mem_file.write_all_at(bytes, remote_address as u64),
+            None => Err(io::Error::last_os_error())
+        }
+    }
+}
+
+impl MemorySource for ProcessSource {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn read_range(&self, physical_address: u32, buffer: &mut [u8]) -> io::Result<()> {
+        self.read_remote(physical_address as usize + self.virtual_address, buffer)
+    }
+
+    fn is_remote(&self) -> bool {
+        true
+    }
+
+    fn virtual_address(&self) -> Option<usize> {
+        Some(self.virtual_address)
+    }
+
+    fn is_alive(&self) -> bool {
+        self.start_time.is_some() && ProcessSource::start_time(self.pid) == self.start_time
+    }
+
+    fn write_range(&mut self, physical_address: u32, bytes: &[u8]) -> io::Result<()> {
+        let write_address = physical_address as usize + self.virtual_address;
+        self.write_remote(write_address, bytes)
+    }
+}
@@ -0,0 +1,259 @@
+mod buffer;
+mod cache;
+mod dump;
+mod error;
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(windows)]
+mod win32;
+
+use std::{cell::RefCell, ffi::CStr, io, mem::MaybeUninit};
+
+use cache::PageCache;
+
+pub use buffer::BufferSource;
+pub use cache::PAGE_SIZE;
+pub use dump::{save_dump, DumpSource};
+pub use error::MemoryError;
+#[cfg(target_os = "linux")]
+pub use linux::ProcessSource;
+#[cfg(windows)]
+pub use win32::ProcessSource;
+
+// Size of the retail Xbox guest RAM.
+pub const XBOX_MEMORY_SIZE: usize = 67108864;
+
+// Longest string read_str will scan for a terminator, tag paths are well below this.
+pub const MAX_STRING_LENGTH: usize = 256;
+
+// A backend that exposes the Xbox guest-physical address space, owned by the sampler thread.
+pub trait MemorySource: Send {
+    fn size(&self) -> usize;
+
+    // Callers guarantee the range lies within size().
+    fn read_range(&self, physical_address: u32, buffer: &mut [u8]) -> io::Result<()>;
+    fn write_range(&mut self, physical_address: u32, bytes: &[u8]) -> io::Result<()>;
+
+    // Host address the guest RAM is mapped at, only meaningful for live processes.
+    fn virtual_address(&self) -> Option<usize> {
+        None
+    }
+
+    // Reads cross into another process, so Memory keeps a page cache in front of this source.
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    // False once the process behind this source has exited, files and buffers never go away.
+    fn is_alive(&self) -> bool {
+        true
+    }
+}
+
+pub struct Memory {
+    source: Box<dyn MemorySource>,
+    cache: Option<RefCell<PageCache>>
+}
+
+impl Memory {
+    pub fn new(source: impl MemorySource + 'static) -> Memory {
+        let cache = source.is_remote().then(|| RefCell::new(PageCache::default()));
+
+        Memory {
+            source: Box::new(source),
+            cache
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.source.size()
+    }
+
+    // Forget what was read so far, the next reads see the current guest RAM.
+    pub fn refresh(&mut self) {
+        if let Some(cache) = &self.cache {
+            cache.borrow_mut().invalidate();
+        }
+    }
+
+    // Pages pulled from a remote source since the last refresh, None for local sources.
+    pub fn pages_read(&self) -> Option<usize> {
+        self.cache.as_ref().map(|cache| cache.borrow().pages_read())
+    }
+
+    pub fn virtual_address(&self) -> Option<usize> {
+        self.source.virtual_address()
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.source.is_alive()
+    }
+
+    // Visit the pages read since the last refresh, which are exactly the ones a snapshot built since then depends on.
+    // Local sources are not cached, so nothing is visited for them.
+    pub fn cached_pages(&self, visit: impl FnMut(u32, &[u8])) {
+        if let Some(cache) = &self.cache {
+            cache.borrow().fresh_pages(visit);
+        }
+    }
+
+    // Copy of the whole guest RAM, read straight from the source without going through the cache.
+    pub fn bytes(&self) -> Result<Vec<u8>, MemoryError> {
+        let mut bytes = vec![0; self.size()];
+        self.source.read_range(0, &mut bytes)
+            .map_err(|e| MemoryError::ReadFailed { address: 0, length: bytes.len(), reason: e.to_string() })?;
+
+        Ok(bytes)
+    }
+
+    // Make sure a range is cached before reading it piecewise, so it is fetched in one go.
+    pub fn prefetch(&self, physical_address: u32, length: usize) -> Result<(), MemoryError> {
+        if self.cache.is_none() {
+            return Ok(());
+        }
+
+        let physical_address = Memory::fix_pointer(physical_address);
+        self.check_range(physical_address, length)?;
+        self.read_range(physical_address, &mut vec![0; length])
+    }
+
+    fn read_range(&self, physical_address: u32, buffer: &mut [u8]) -> Result<(), MemoryError> {
+        let result = match &self.cache {
+            Some(cache) => cache.borrow_mut().read(self.source.as_ref(), physical_address, buffer),
+            None => self.source.read_range(physical_address, buffer)
+        };
+
+        result.map_err(|e| MemoryError::ReadFailed { address: physical_address, length: buffer.len(), reason: e.to_string() })
+    }
+
+    fn check_range(&self, physical_address: u32, length: usize) -> Result<(), MemoryError> {
+        let end = physical_address as usize + length;
+        if end > self.size() {
+            return Err(MemoryError::OutOfRange { address: physical_address, length });
+        }
+
+        Ok(())
+    }
+
+    // Only use with plain-old-data structs, every bit pattern read from the guest must be a valid T.
+    pub fn read<T>(&self, physical_address: u32) -> Result<T, MemoryError> {
+        let physical_address = Memory::fix_pointer(physical_address);
+        self.check_range(physical_address, size_of::<T>())?;
+
+        if !(physical_address as usize).is_multiple_of(align_of::<T>()) {
+            return Err(MemoryError::Misaligned { address: physical_address, alignment: align_of::<T>() });
+        }
+
+        let mut value = MaybeUninit::<T>::uninit();
+        unsafe {
+            let buffer = std::slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, size_of::<T>());
+            self.read_range(physical_address, buffer)?;
+            Ok(value.assume_init())
+        }
+    }
+
+    pub fn read_str(&self, physical_address: u32) -> Result<String, MemoryError> {
+        let physical_address = Memory::fix_pointer(physical_address);
+        if physical_address as usize >= self.size() {
+            return Err(MemoryError::OutOfRange { address: physical_address, length: 1 });
+        }
+
+        let length = MAX_STRING_LENGTH.min(self.size() - physical_address as usize);
+        let mut bytes = vec![0; length];
+        self.read_range(physical_address, &mut bytes)?;
+
+        let value = CStr::from_bytes_until_nul(&bytes)
+            .map_err(|_| MemoryError::UnterminatedString { address: physical_address, max_length: length })?;
+
+        value.to_str()
+            .map(|value| value.to_string())
+            .map_err(|_| MemoryError::InvalidUtf8 { address: physical_address })
+    }
+
+    // Takes the same addresses as read, guest pointers included.
+    pub fn write(&mut self, physical_address: u32, write_bytes: &[u8]) -> Result<(), MemoryError> {
+        let physical_address = Memory::fix_pointer(physical_address);
+        self.check_range(physical_address, write_bytes.len())?;
+
+        if let Some(cache) = &self.cache {
+            cache.borrow_mut().invalidate_range(physical_address, write_bytes.len());
+        }
+
+        self.source.write_range(physical_address, write_bytes)
+            .map_err(|e| MemoryError::WriteFailed {
+                address: physical_address,
+                length: write_bytes.len(),
+                reason: e.to_string()
+            })
+    }
+
+    pub fn fix_pointer(ptr: u32) -> u32 {
+        let mut ptr = ptr.to_le_bytes();
+        ptr[3] = 0x0;
+        u32::from_le_bytes(ptr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 64 bytes counting up from 0, so every read can be checked against its address.
+    fn memory() -> Memory {
+        Memory::new(BufferSource::new((0..64).collect()))
+    }
+
+    #[test]
+    fn reads_up_to_the_last_byte() {
+        let memory = memory();
+        assert_eq!(memory.read::<u32>(60), Ok(u32::from_le_bytes([60, 61, 62, 63])));
+        assert_eq!(memory.read::<u8>(63), Ok(63));
+    }
+
+    #[test]
+    fn reads_past_the_end_are_out_of_range() {
+        let memory = memory();
+        assert_eq!(memory.read::<u32>(62), Err(MemoryError::OutOfRange { address: 62, length: 4 }));
+        assert_eq!(memory.read::<u8>(64), Err(MemoryError::OutOfRange { address: 64, length: 1 }));
+        assert_eq!(memory.read_str(64), Err(MemoryError::OutOfRange { address: 64, length: 1 }));
+    }
+
+    #[test]
+    fn unaligned_reads_are_rejected() {
+        let memory = memory();
+        assert_eq!(memory.read::<u32>(2), Err(MemoryError::Misaligned { address: 2, alignment: 4 }));
+        assert_eq!(memory.read::<u16>(3), Err(MemoryError::Misaligned { address: 3, alignment: 2 }));
+    }
+
+    #[test]
+    fn strings_end_at_the_terminator() {
+        let mut bytes = vec![0; 64];
+        bytes[8..13].copy_from_slice(b"halo\0");
+        let memory = Memory::new(BufferSource::new(bytes));
+
+        assert_eq!(memory.read_str(8), Ok("halo".to_string()));
+        assert_eq!(memory.read_str(12), Ok(String::new()));
+    }
+
+    #[test]
+    fn string_running_off_the_end_is_unterminated() {
+        let memory = memory();
+        assert_eq!(memory.read_str(60), Err(MemoryError::UnterminatedString { address: 60, max_length: 4 }));
+    }
+
+    #[test]
+    fn guest_pointers_read_and_write_the_same_address() {
+        let mut memory = memory();
+        assert_eq!(memory.read::<u32>(0x8000_0010), memory.read::<u32>(0x10));
+
+        memory.write(0x8000_0010, &[0xAA; 4]).unwrap();
+        assert_eq!(memory.read::<u32>(0x10), Ok(0xAAAA_AAAA));
+    }
+
+    #[test]
+    fn writes_past_the_end_are_out_of_range() {
+        let mut memory = memory();
+        assert_eq!(memory.write(62, &[0; 4]), Err(MemoryError::OutOfRange { address: 62, length: 4 }));
+        assert_eq!(memory.read::<u16>(62), Ok(u16::from_le_bytes([62, 63])));
+    }
+}
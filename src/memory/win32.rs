@@ -0,0 +1,137 @@
+use std::{ffi::c_void, io};
+
+use windows::Win32::{
+    Foundation::{HANDLE, WAIT_TIMEOUT},
+    System::{
+        Memory::{MEMORY_BASIC_INFORMATION, MEM_COMMIT, MEM_MAPPED, MEM_PRIVATE, PAGE_READWRITE},
+        Threading::{PROCESS_ALL_ACCESS, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ}
+    }
+};
+
+use super::MemorySource;
+
+// Reads the guest RAM of a running xemu.exe through ReadProcessMemory.
+pub struct ProcessSource {
+    size: usize,
+    virtual_address: usize,
+    pid: u32,
+    handle: HANDLE
+}
+
+impl ProcessSource {
+    pub fn new(virtual_address: usize, capacity: usize, pid: u32) -> io::Result<ProcessSource> {
+        let handle = unsafe { windows::Win32::System::Threading::OpenProcess(PROCESS_ALL_ACCESS, false, pid) }
+            .map_err(io::Error::other)?;
+
+        Ok(ProcessSource {
+            size: capacity,
+            pid,
+            virtual_address,
+            handle
+        })
+    }
+
+    // Base addresses of every committed read-write allocation in the process that is exactly `size` bytes.
+    pub fn find_regions(pid: u32, size: usize) -> Vec<usize> {
+        let handle = unsafe { windows::Win32::System::Threading::OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, pid) };
+        let Ok(handle) = handle else { return Vec::new(); };
+
+        // A single allocation can be reported as several regions, so sum them up per allocation base.
+        let mut allocations: Vec<(usize, usize)> = Vec::new();
+        let mut address = 0usize;
+        loop {
+            let mut info = MEMORY_BASIC_INFORMATION::default();
+            let written = unsafe {
+                windows::Win32::System::Memory::VirtualQueryEx(
+                    handle,
+                    Some(address as *const c_void),
+                    &mut info,
+                    size_of::<MEMORY_BASIC_INFORMATION>()
+                )
+            };
+
+            if written == 0 { break; }
+
+            let is_candidate = info.State == MEM_COMMIT
+                && (info.Type == MEM_PRIVATE || info.Type == MEM_MAPPED)
+                && info.Protect == PAGE_READWRITE;
+
+            if is_candidate {
+                let allocation_base = info.AllocationBase as usize;
+                match allocations.last_mut() {
+                    Some((base, length)) if *base == allocation_base => *length += info.RegionSize,
+                    _ => allocations.push((allocation_base, info.RegionSize))
+                }
+            }
+
+            address = info.BaseAddress as usize + info.RegionSize;
+        }
+
+        unsafe {
+            let _ = windows::Win32::Foundation::CloseHandle(handle);
+        }
+
+        allocations.into_iter()
+            .filter(|(_, length)| *length == size)
+            .map(|(base, _)| base)
+            .collect()
+    }
+}
+
+// The process handle is only a kernel object reference, it can be used from any thread.
+unsafe impl Send for ProcessSource {}
+
+impl MemorySource for ProcessSource {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn read_range(&self, physical_address: u32, buffer: &mut [u8]) -> io::Result<()> {
+        let read_address = physical_address as usize + self.virtual_address;
+        unsafe {
+            windows::Win32::System::Diagnostics::Debug::ReadProcessMemory(
+                self.handle,
+                read_address as *const c_void,
+                buffer.as_mut_ptr() as *mut c_void,
+                buffer.len(),
+                None
+            ).map_err(io::Error::other)
+        }
+    }
+
+    fn is_remote(&self) -> bool {
+        true
+    }
+
+    fn virtual_address(&self) -> Option<usize> {
+        Some(self.virtual_address)
+    }
+
+    fn is_alive(&self) -> bool {
+        // The handle keeps the process object around, it only becomes signaled once xemu exits.
+        unsafe { windows::Win32::System::Threading::WaitForSingleObject(self.handle, 0) == WAIT_TIMEOUT }
+    }
+
+    fn write_range(&mut self, physical_address: u32, bytes: &[u8]) -> io::Result<()> {
+        let write_address = physical_address as usize + self.virtual_address;
+        unsafe {
+            windows::Win32::System::Diagnostics::Debug::WriteProcessMemory(
+                self.handle,
+                write_address as *const c_void,
+                bytes.as_ptr() as *mut c_void,
+                bytes.len(),
+                None
+            ).map_err(io::Error::other)
+        }
+    }
+}
+
+impl Drop for ProcessSource {
+    fn drop(&mut self) {
+        if !self.handle.is_invalid() {
+            unsafe {
+                let _ = windows::Win32::Foundation::CloseHandle(self.handle);
+            }
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::io::{self, Read, Write};
+
+// File layout, all little endian:
+//   header:  magic, version, map name, difficulty, random seed, page size, memory size
+//   frames:  kind, local_time, page count, then per page: page index, base, encoded length, encoded bytes
+// Pages hold the xor against the last recorded version of that page (or zero), run length encoded.
+pub const MAGIC: &[u8; 4] = b"OVRC";
+pub const VERSION: u16 = 1;
+
+// Every this many frames a keyframe writes the pages that snapshot read in full rather than against earlier frames,
+// so replay can seek without starting from the top. It is not a full memory image, pages nothing read are left out.
+pub const KEYFRAME_INTERVAL: u32 = 300;
+
+// Runs of fewer unchanged bytes than this are cheaper to keep inside a literal.
+const MIN_ZERO_RUN: usize = 4;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum FrameKind {
+    Delta = 0,
+    Keyframe = 1
+}
+
+// What an encoded page is applied on top of.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum PageBase {
+    Zero = 0,
+    Previous = 1
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordingHeader {
+    pub map_name: String,
+    pub difficulty: i16,
+    pub random_seed: i32,
+    pub page_size: u32,
+    pub memory_size: u32
+}
+
+impl FrameKind {
+    pub fn from_u8(value: u8) -> io::Result<FrameKind> {
+        match value {
+            0 => Ok(FrameKind::Delta),
+            1 => Ok(FrameKind::Keyframe),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("unknown frame kind {}", value)))
+        }
+    }
+}
+
+impl PageBase {
+    pub fn from_u8(value: u8) -> io::Result<PageBase> {
+        match value {
+            0 => Ok(PageBase::Zero),
+            1 => Ok(PageBase::Previous),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("unknown page base {}", value)))
+        }
+    }
+}
+
+pub fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+impl RecordingHeader {
+    pub fn read(reader: &mut impl Read) -> io::Result<RecordingHeader> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a recording"));
+        }
+
+        let version = u16::from_le_bytes(read_array(reader)?);
+        if version != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsupported recording version {}", version)));
+        }
+
+        let mut map_name = vec![0; u16::from_le_bytes(read_array(reader)?) as usize];
+        reader.read_exact(&mut map_name)?;
+
+        Ok(RecordingHeader {
+            map_name: String::from_utf8(map_name).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            difficulty: i16::from_le_bytes(read_array(reader)?),
+            random_seed: i32::from_le_bytes(read_array(reader)?),
+            page_size: u32::from_le_bytes(read_array(reader)?),
+            memory_size: u32::from_le_bytes(read_array(reader)?)
+        })
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.map_name.len() as u16).to_le_bytes())?;
+        writer.write_all(self.map_name.as_bytes())?;
+        writer.write_all(&self.difficulty.to_le_bytes())?;
+        writer.write_all(&self.random_seed.to_le_bytes())?;
+        writer.write_all(&self.page_size.to_le_bytes())?;
+        writer.write_all(&self.memory_size.to_le_bytes())
+    }
+}
+
+// Xor `page` against `previous` (or zero) and run length encode the result as
+// repeated [unchanged byte count: u16][changed byte count: u16][changed bytes].
+pub fn encode_page(previous: Option<&[u8]>, page: &[u8], encoded: &mut Vec<u8>) {
+    let delta = |index: usize| page[index] ^ previous.map_or(0, |previous| previous[index]);
+    let length = page.len();
+
+    let mut index = 0;
+    while index < length {
+        let zero_start = index;
+        while index < length && delta(index) == 0 {
+            index += 1;
+        }
+
+        // The literal ends at the end of the page or at the next run of zeros worth skipping.
+        let literal_start = index;
+        while index < length {
+            let run = (index..length).take_while(|&run_index| delta(run_index) == 0).take(MIN_ZERO_RUN).count();
+            if run >= MIN_ZERO_RUN || index + run == length && run > 0 {
+                break;
+            }
+            index += run.max(1);
+        }
+
+        encoded.extend_from_slice(&((literal_start - zero_start) as u16).to_le_bytes());
+        encoded.extend_from_slice(&((index - literal_start) as u16).to_le_bytes());
+        encoded.extend((literal_start..index).map(delta));
+    }
+}
+
+// Undo encode_page, `page` holds the previous version of the page when `base` is Previous.
+pub fn decode_page(base: PageBase, mut encoded: &[u8], page: &mut [u8]) -> io::Result<()> {
+    if base == PageBase::Zero {
+        page.fill(0);
+    }
+
+    let corrupt = || io::Error::new(io::ErrorKind::InvalidData, "corrupt page in recording");
+
+    let mut index = 0;
+    while !encoded.is_empty() {
+        let zero_run = u16::from_le_bytes(read_array(&mut encoded)?) as usize;
+        let literal_length = u16::from_le_bytes(read_array(&mut encoded)?) as usize;
+
+        index += zero_run;
+        if index + literal_length > page.len() || literal_length > encoded.len() {
+            return Err(corrupt());
+        }
+
+        let (literal, rest) = encoded.split_at(literal_length);
+        for (byte, delta) in page[index..index + literal_length].iter_mut().zip(literal) {
+            *byte ^= delta;
+        }
+
+        index += literal_length;
+        encoded = rest;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Small xorshift so the pages are the same on every run.
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        // Mostly unchanged bytes with runs of changes of every length, like a page between two ticks.
+        fn page_from(&mut self, previous: &[u8]) -> Vec<u8> {
+            let mut page = previous.to_vec();
+            let changes = self.next() % 64;
+            for _ in 0..changes {
+                let start = (self.next() % page.len() as u64) as usize;
+                let length = (self.next() % 16) as usize;
+                for byte in page.iter_mut().skip(start).take(length) {
+                    *byte = self.next() as u8;
+                }
+            }
+            page
+        }
+    }
+
+    fn round_trip(previous: Option<&[u8]>, page: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        encode_page(previous, page, &mut encoded);
+
+        let (base, mut decoded) = match previous {
+            Some(previous) => (PageBase::Previous, previous.to_vec()),
+            // Whatever is in the buffer has to be cleared for a page against zero.
+            None => (PageBase::Zero, vec![0xAA; page.len()])
+        };
+        decode_page(base, &encoded, &mut decoded).unwrap();
+        decoded
+    }
+
+    #[test]
+    fn pages_round_trip() {
+        let mut random = Random(0x5eed);
+        let mut previous = vec![0; 4096];
+
+        for _ in 0..2000 {
+            let page = random.page_from(&previous);
+            assert_eq!(round_trip(Some(&previous), &page), page);
+            assert_eq!(round_trip(None, &page), page);
+            previous = page;
+        }
+    }
+
+    #[test]
+    fn edge_pages_round_trip() {
+        let zero = vec![0; 4096];
+        let full = vec![0xFF; 4096];
+        let mut alternating = zero.clone();
+        for byte in alternating.iter_mut().step_by(2) {
+            *byte = 1;
+        }
+        // A change in the last byte, right after a short run of unchanged bytes.
+        let mut last = zero.clone();
+        last[4093] = 1;
+        last[4095] = 1;
+
+        for page in [&zero, &full, &alternating, &last] {
+            assert_eq!(round_trip(None, page), *page);
+            assert_eq!(round_trip(Some(&zero), page), *page);
+            assert_eq!(round_trip(Some(&full), page), *page);
+        }
+    }
+
+    #[test]
+    fn unchanged_page_encodes_as_a_single_run() {
+        let page = vec![7; 4096];
+        let mut encoded = Vec::new();
+        encode_page(Some(&page), &page, &mut encoded);
+
+        assert_eq!(encoded, [0x00, 0x10, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn corrupt_page_is_rejected() {
+        let mut page = vec![0; 4096];
+
+        // A literal running past the end of the page.
+        let encoded = [0xFF, 0x0F, 0x10, 0x00, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        assert!(decode_page(PageBase::Zero, &encoded, &mut page).is_err());
+
+        // A literal longer than the bytes that follow it.
+        let encoded = [0x00, 0x00, 0x04, 0x00, 1, 2];
+        assert!(decode_page(PageBase::Zero, &encoded, &mut page).is_err());
+    }
+
+    #[test]
+    fn header_round_trips() {
+        let header = RecordingHeader {
+            map_name: "a10".to_string(),
+            difficulty: 3,
+            random_seed: -12345,
+            page_size: 4096,
+            memory_size: 64 * 1024 * 1024
+        };
+
+        let mut bytes = Vec::new();
+        header.write(&mut bytes).unwrap();
+        let read = RecordingHeader::read(&mut &bytes[..]).unwrap();
+
+        assert_eq!(read.map_name, header.map_name);
+        assert_eq!(read.difficulty, header.difficulty);
+        assert_eq!(read.random_seed, header.random_seed);
+        assert_eq!(read.page_size, header.page_size);
+        assert_eq!(read.memory_size, header.memory_size);
+    }
+}
@@ -0,0 +1,6 @@
+mod format;
+mod recorder;
+mod replay;
+
+pub use recorder::Recorder;
+pub use replay::Replay;
@@ -0,0 +1,135 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf}
+};
+
+use crate::{
+    engine::EngineSnapshot,
+    export::{write_object_rows, OBJECTS_CSV_HEADER},
+    memory::{Memory, PAGE_SIZE}
+};
+
+use super::format::{encode_page, FrameKind, PageBase, RecordingHeader, KEYFRAME_INTERVAL};
+
+// Appends the guest pages behind every sampled snapshot to a recording file.
+pub struct Recorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    header: Option<RecordingHeader>,
+    // Last recorded version of every page since the latest keyframe, deltas are taken against these.
+    recorded_pages: HashMap<u32, Box<[u8]>>,
+    last_tick: Option<u32>,
+    frames: u32,
+    objects_csv: Option<BufWriter<File>>
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Recorder> {
+        Ok(Recorder {
+            path: path.as_ref().to_path_buf(),
+            writer: BufWriter::new(File::create(path.as_ref())?),
+            header: None,
+            recorded_pages: HashMap::new(),
+            last_tick: None,
+            frames: 0,
+            objects_csv: None
+        })
+    }
+
+    // Also append the Objects table of every recorded tick to a CSV, free slots are left out.
+    pub fn append_objects_csv(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut objects_csv = BufWriter::new(File::create(path)?);
+        writeln!(objects_csv, "{}", OBJECTS_CSV_HEADER)?;
+
+        self.objects_csv = Some(objects_csv);
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    // Append a frame for `snapshot`, which must have been built from `memory` since its last refresh.
+    pub fn record(&mut self, memory: &Memory, snapshot: &EngineSnapshot) -> io::Result<()> {
+        let game_options = &snapshot.game_globals.game_options;
+        let tick = snapshot.game_time_globals.local_time;
+
+        match &self.header {
+            None => {
+                let header = RecordingHeader {
+                    map_name: snapshot.map_name.clone(),
+                    difficulty: game_options.difficulty,
+                    random_seed: game_options.random_seed,
+                    page_size: PAGE_SIZE as u32,
+                    memory_size: memory.size() as u32
+                };
+
+                header.write(&mut self.writer)?;
+                self.header = Some(header);
+            }
+            // A recording covers a single map, the header could not describe anything else.
+            Some(header) if header.map_name != snapshot.map_name => {
+                return Err(io::Error::other(format!("map changed from {} to {}", header.map_name, snapshot.map_name)));
+            }
+            Some(_) => {}
+        }
+
+        // Resampling a paused game gives the same tick again, which replay has no use for.
+        if self.last_tick == Some(tick) {
+            return Ok(());
+        }
+
+        let kind = if self.frames.is_multiple_of(KEYFRAME_INTERVAL) { FrameKind::Keyframe } else { FrameKind::Delta };
+        if kind == FrameKind::Keyframe {
+            self.recorded_pages.clear();
+        }
+
+        let mut frame = Vec::new();
+        let mut page_count = 0u32;
+        memory.cached_pages(|page, bytes| {
+            let previous = self.recorded_pages.get(&page);
+            if previous.is_some_and(|previous| **previous == *bytes) {
+                return;
+            }
+
+            let base = if previous.is_some() { PageBase::Previous } else { PageBase::Zero };
+            let mut encoded = Vec::new();
+            encode_page(previous.map(|previous| &previous[..]), bytes, &mut encoded);
+
+            frame.extend_from_slice(&page.to_le_bytes());
+            frame.push(base as u8);
+            frame.extend_from_slice(&(encoded.len() as u32).to_le_bytes());
+            frame.extend_from_slice(&encoded);
+            page_count += 1;
+
+            self.recorded_pages.insert(page, bytes.into());
+        });
+
+        self.writer.write_all(&[kind as u8])?;
+        self.writer.write_all(&tick.to_le_bytes())?;
+        self.writer.write_all(&page_count.to_le_bytes())?;
+        self.writer.write_all(&frame)?;
+
+        if let Some(objects_csv) = self.objects_csv.as_mut() {
+            write_object_rows(objects_csv, snapshot, false)?;
+        }
+
+        self.last_tick = Some(tick);
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        if let Some(objects_csv) = self.objects_csv.as_mut() {
+            objects_csv.flush()?;
+        }
+
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,327 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    time::{Duration, Instant}
+};
+
+use crate::{
+    engine::{build_snapshot, EngineSnapshot, SnapshotError},
+    memory::{BufferSource, Memory, PAGE_SIZE}
+};
+
+use super::format::{decode_page, read_array, FrameKind, PageBase, RecordingHeader};
+
+// Halo runs at 30 ticks per second.
+const TICK_DURATION: Duration = Duration::from_micros(33333);
+
+struct FrameIndex {
+    kind: FrameKind,
+    tick: u32,
+    page_count: u32,
+    // Offset of the first page of the frame in the recording.
+    offset: usize
+}
+
+// A recording loaded for playback, rebuilding the guest pages of whichever frame it is positioned at.
+pub struct Replay {
+    path: PathBuf,
+    header: RecordingHeader,
+    data: Vec<u8>,
+    frames: Vec<FrameIndex>,
+    memory: Memory,
+    position: Option<usize>,
+    playing: bool,
+    last_step: Instant
+}
+
+impl Replay {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Replay> {
+        let data = fs::read(path.as_ref())?;
+        let mut reader = &data[..];
+
+        let header = RecordingHeader::read(&mut reader)?;
+        if header.page_size as usize != PAGE_SIZE {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsupported page size {}", header.page_size)));
+        }
+
+        // A recording that was cut short, e.g. by a crash, ends at its last complete frame.
+        let mut frames = Vec::new();
+        while let Ok(frame) = Replay::index_frame(&data, &mut reader, &header) {
+            frames.push(frame);
+        }
+
+        if frames.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "recording has no frames"));
+        }
+
+        let memory = Memory::new(BufferSource::zeroed(header.memory_size as usize));
+        let mut replay = Replay {
+            path: path.as_ref().to_path_buf(),
+            header,
+            data,
+            frames,
+            memory,
+            position: None,
+            playing: false,
+            last_step: Instant::now()
+        };
+
+        replay.go_to(0)?;
+        Ok(replay)
+    }
+
+    // Check the frame at `reader` is complete and skip over its pages.
+    fn index_frame(data: &[u8], reader: &mut &[u8], header: &RecordingHeader) -> io::Result<FrameIndex> {
+        let kind = FrameKind::from_u8(read_array::<1>(reader)?[0])?;
+        let tick = u32::from_le_bytes(read_array(reader)?);
+        let page_count = u32::from_le_bytes(read_array(reader)?);
+        let offset = data.len() - reader.len();
+
+        for _ in 0..page_count {
+            let page = u32::from_le_bytes(read_array(reader)?);
+            PageBase::from_u8(read_array::<1>(reader)?[0])?;
+            let length = u32::from_le_bytes(read_array(reader)?) as usize;
+
+            if (page as usize + 1) * PAGE_SIZE > header.memory_size as usize || length > reader.len() {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "incomplete frame"));
+            }
+
+            *reader = &reader[length..];
+        }
+
+        Ok(FrameIndex { kind, tick, page_count, offset })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn header(&self) -> &RecordingHeader {
+        &self.header
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn tick(&self, frame: usize) -> u32 {
+        self.frames[frame].tick
+    }
+
+    // None after a failed seek, until the next one succeeds.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    pub fn seek(&mut self, frame: usize) -> io::Result<()> {
+        self.last_step = Instant::now();
+        self.go_to(frame)
+    }
+
+    fn go_to(&mut self, frame: usize) -> io::Result<()> {
+        let frame = frame.min(self.frames.len() - 1);
+
+        // Stepping forward only applies the frames in between, anything else starts over from the nearest keyframe.
+        let first = match self.position {
+            Some(position) if position < frame && self.frames[position + 1..=frame].iter().all(|frame| frame.kind == FrameKind::Delta) => position + 1,
+            _ => (0..=frame).rev().find(|&index| self.frames[index].kind == FrameKind::Keyframe).unwrap_or(0)
+        };
+
+        self.position = None;
+        for index in first..=frame {
+            self.apply_frame(index)?;
+        }
+
+        self.position = Some(frame);
+        Ok(())
+    }
+
+    fn apply_frame(&mut self, frame: usize) -> io::Result<()> {
+        let mut reader = &self.data[self.frames[frame].offset..];
+
+        for _ in 0..self.frames[frame].page_count {
+            let page = u32::from_le_bytes(read_array(&mut reader)?);
+            let base = PageBase::from_u8(read_array::<1>(&mut reader)?[0])?;
+            let mut encoded = vec![0; u32::from_le_bytes(read_array(&mut reader)?) as usize];
+            reader.read_exact(&mut encoded)?;
+
+            let address = page * PAGE_SIZE as u32;
+            let mut bytes: [u8; PAGE_SIZE] = self.memory.read(address).map_err(io::Error::other)?;
+            decode_page(base, &encoded, &mut bytes)?;
+            self.memory.write(address, &bytes).map_err(io::Error::other)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> Result<EngineSnapshot, SnapshotError> {
+        build_snapshot(&self.memory)
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn play(&mut self) -> io::Result<()> {
+        // Playing from the last frame starts over.
+        if self.position == Some(self.frames.len() - 1) {
+            self.go_to(0)?;
+        }
+        self.playing = true;
+        self.last_step = Instant::now();
+        Ok(())
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    // Move on by however many frames are due at real time speed, returns whether the position changed.
+    pub fn update(&mut self) -> io::Result<bool> {
+        if !self.playing {
+            return Ok(false);
+        }
+
+        let Some(position) = self.position else {
+            self.playing = false;
+            return Ok(false);
+        };
+
+        let mut frame = position;
+        while frame + 1 < self.frames.len() {
+            let ticks = self.frames[frame + 1].tick.saturating_sub(self.frames[frame].tick).max(1);
+            let due = self.last_step + TICK_DURATION * ticks;
+            if due > Instant::now() {
+                break;
+            }
+
+            self.last_step = due;
+            frame += 1;
+        }
+
+        if frame + 1 == self.frames.len() {
+            self.playing = false;
+        }
+
+        if frame == position {
+            return Ok(false);
+        }
+
+        self.go_to(frame)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recording::format::encode_page;
+
+    const MEMORY_SIZE: usize = 4 * PAGE_SIZE;
+
+    fn page_filled(value: u8) -> Vec<u8> {
+        vec![value; PAGE_SIZE]
+    }
+
+    // Page index, the version it is a delta against (none for zero) and its bytes.
+    type FramePage<'a> = (u32, Option<&'a [u8]>, &'a [u8]);
+
+    fn write_frame(data: &mut Vec<u8>, kind: FrameKind, tick: u32, pages: &[FramePage]) {
+        data.push(kind as u8);
+        data.extend_from_slice(&tick.to_le_bytes());
+        data.extend_from_slice(&(pages.len() as u32).to_le_bytes());
+
+        for (page, previous, bytes) in pages {
+            let mut encoded = Vec::new();
+            encode_page(*previous, bytes, &mut encoded);
+
+            data.extend_from_slice(&page.to_le_bytes());
+            data.push(if previous.is_some() { PageBase::Previous } else { PageBase::Zero } as u8);
+            data.extend_from_slice(&(encoded.len() as u32).to_le_bytes());
+            data.extend_from_slice(&encoded);
+        }
+    }
+
+    // A keyframe and two deltas, along with the length of the file after each frame.
+    fn recording() -> (Vec<u8>, Vec<usize>) {
+        let header = RecordingHeader {
+            map_name: "a10".to_string(),
+            difficulty: 2,
+            random_seed: 42,
+            page_size: PAGE_SIZE as u32,
+            memory_size: MEMORY_SIZE as u32
+        };
+
+        let mut data = Vec::new();
+        header.write(&mut data).unwrap();
+        let mut frame_ends = Vec::new();
+
+        let mut changed = page_filled(2);
+        changed[100..110].fill(9);
+
+        write_frame(&mut data, FrameKind::Keyframe, 10, &[(0, None, &page_filled(1)), (2, None, &page_filled(2))]);
+        frame_ends.push(data.len());
+        write_frame(&mut data, FrameKind::Delta, 11, &[(2, Some(&page_filled(2)), &changed)]);
+        frame_ends.push(data.len());
+        write_frame(&mut data, FrameKind::Delta, 13, &[(1, None, &page_filled(3))]);
+        frame_ends.push(data.len());
+
+        (data, frame_ends)
+    }
+
+    fn open(name: &str, data: &[u8]) -> io::Result<Replay> {
+        let path = std::env::temp_dir().join(format!("objectviewer-{}-{}.ovr", std::process::id(), name));
+        fs::write(&path, data).unwrap();
+        let replay = Replay::open(&path);
+        fs::remove_file(&path).unwrap();
+        replay
+    }
+
+    fn page(replay: &Replay, page: u32) -> [u8; PAGE_SIZE] {
+        replay.memory.read(page * PAGE_SIZE as u32).unwrap()
+    }
+
+    #[test]
+    fn frames_are_rebuilt() {
+        let (data, _) = recording();
+        let mut replay = open("frames", &data).unwrap();
+
+        assert_eq!(replay.header().map_name, "a10");
+        assert_eq!(replay.frame_count(), 3);
+        assert_eq!((replay.tick(0), replay.tick(1), replay.tick(2)), (10, 11, 13));
+
+        replay.seek(2).unwrap();
+        assert_eq!(page(&replay, 0), *page_filled(1));
+        assert_eq!(page(&replay, 1), *page_filled(3));
+        assert_eq!(page(&replay, 2)[100..110], [9; 10]);
+        assert_eq!(page(&replay, 2)[110], 2);
+        assert_eq!(page(&replay, 3), *page_filled(0));
+
+        // Seeking back starts over from the keyframe.
+        replay.seek(0).unwrap();
+        assert_eq!(page(&replay, 2), *page_filled(2));
+    }
+
+    #[test]
+    fn truncated_recording_ends_at_last_complete_frame() {
+        let (data, frame_ends) = recording();
+
+        for (complete_frames, end) in frame_ends.iter().enumerate() {
+            // Cut in the middle of the next frame, and right at its end.
+            for length in [end - 1, *end] {
+                let expected = if length == *end { complete_frames + 1 } else { complete_frames };
+                match open(&format!("truncated-{}", length), &data[..length]) {
+                    Ok(replay) => assert_eq!(replay.frame_count(), expected, "cut at {}", length),
+                    Err(e) => assert_eq!(expected, 0, "cut at {}: {}", length, e)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_header_is_rejected() {
+        let (data, _) = recording();
+        assert!(open("header", &data[..10]).is_err());
+    }
+}
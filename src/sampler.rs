@@ -0,0 +1,240 @@
+use std::{
+    path::PathBuf,
+    sync::{mpsc::{self, Receiver, RecvTimeoutError, Sender}, Arc, Mutex, MutexGuard},
+    thread,
+    time::{Duration, Instant}
+};
+
+use crate::{
+    engine::{build_snapshot, read_local_time, EngineSnapshot, SnapshotError},
+    memory::Memory,
+    recording::Recorder
+};
+
+// How often the game tick is polled when sampling every game tick.
+const TICK_POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+// While the tick cannot be read, e.g. during a map load, sample this often so the status still shows up.
+const NO_TICK_SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum SampleRate {
+    // Exactly one snapshot per GameTimeGlobals::local_time, skipped ticks are reported as dropped.
+    GameTick,
+    Fixed(Duration)
+}
+
+pub const SAMPLE_RATES: [(&str, SampleRate); 5] = [
+    ("Every Game Tick", SampleRate::GameTick),
+    ("60 Hz", SampleRate::Fixed(Duration::from_micros(16667))),
+    ("30 Hz", SampleRate::Fixed(Duration::from_micros(33333))),
+    ("10 Hz", SampleRate::Fixed(Duration::from_millis(100))),
+    ("1 Hz", SampleRate::Fixed(Duration::from_secs(1)))
+];
+
+impl SampleRate {
+    fn interval(&self) -> Duration {
+        match self {
+            SampleRate::GameTick => TICK_POLL_INTERVAL,
+            SampleRate::Fixed(interval) => *interval
+        }
+    }
+}
+
+enum SamplerCommand {
+    SetRate(SampleRate),
+    // Sample again even though the game did not tick, e.g. after we wrote to a paused game.
+    Resample,
+    StartRecording(Recorder),
+    StopRecording,
+    Stop
+}
+
+pub enum SamplerEvent {
+    Sample {
+        snapshot: Result<Box<EngineSnapshot>, SnapshotError>,
+        // Pages pulled from the emulator to build this snapshot, None for dumps.
+        pages_read: Option<usize>,
+        // Ticks that passed without a snapshot since the previous one, always 0 unless sampling every game tick.
+        dropped_ticks: u32
+    },
+    RecordingStopped {
+        path: PathBuf,
+        frames: u32,
+        error: Option<String>
+    },
+    // The process behind the memory went away, the sampler has stopped.
+    Exited
+}
+
+// Refreshes memory and builds snapshots on its own thread, so a slow read never stalls the window.
+pub struct Sampler {
+    memory: Arc<Mutex<Memory>>,
+    commands: Sender<SamplerCommand>,
+    events: Receiver<SamplerEvent>
+}
+
+impl Sampler {
+    pub fn spawn(memory: Memory, rate: SampleRate) -> Sampler {
+        let memory = Arc::new(Mutex::new(memory));
+        let (command_sender, command_receiver) = mpsc::channel();
+        let (event_sender, event_receiver) = mpsc::channel();
+
+        let thread_memory = memory.clone();
+        thread::spawn(move || run(thread_memory, rate, command_receiver, event_sender));
+
+        Sampler {
+            memory,
+            commands: command_sender,
+            events: event_receiver
+        }
+    }
+
+    // Blocks while a snapshot is being built, keep the guard short.
+    pub fn memory(&self) -> MutexGuard<'_, Memory> {
+        self.memory.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // For callers that outlive a borrow of the sampler, e.g. scripts.
+    pub fn shared_memory(&self) -> Arc<Mutex<Memory>> {
+        self.memory.clone()
+    }
+
+    pub fn set_rate(&self, rate: SampleRate) {
+        let _ = self.commands.send(SamplerCommand::SetRate(rate));
+    }
+
+    pub fn resample(&self) {
+        let _ = self.commands.send(SamplerCommand::Resample);
+    }
+
+    // Record every following snapshot, until stopped or the recorder fails.
+    pub fn start_recording(&self, recorder: Recorder) {
+        let _ = self.commands.send(SamplerCommand::StartRecording(recorder));
+    }
+
+    pub fn stop_recording(&self) {
+        let _ = self.commands.send(SamplerCommand::StopRecording);
+    }
+
+    // Everything the sampler produced since the last call, oldest first.
+    pub fn events(&self) -> impl Iterator<Item = SamplerEvent> + '_ {
+        self.events.try_iter()
+    }
+
+    // Block until the sampler produces something, None once it has stopped.
+    pub fn wait_event(&self) -> Option<SamplerEvent> {
+        self.events.recv().ok()
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        // The thread holds its own reference to the memory, it is released once the thread sees this.
+        let _ = self.commands.send(SamplerCommand::Stop);
+    }
+}
+
+// Flush the recorder and tell the UI it stopped, with `error` as the reason if it did not stop on request.
+fn stop_recording(recorder: Recorder, error: Option<String>, events: &Sender<SamplerEvent>) {
+    let path = recorder.path().to_path_buf();
+    let frames = recorder.frames();
+    let error = error.or_else(|| recorder.finish().err().map(|e| e.to_string()));
+
+    let _ = events.send(SamplerEvent::RecordingStopped { path, frames, error });
+}
+
+fn run(memory: Arc<Mutex<Memory>>, mut rate: SampleRate, commands: Receiver<SamplerCommand>, events: Sender<SamplerEvent>) {
+    let mut recorder: Option<Recorder> = None;
+    let mut next_poll = Instant::now();
+    let mut last_sample: Option<Instant> = None;
+    let mut last_tick: Option<u32> = None;
+    let mut resample = false;
+
+    loop {
+        match commands.recv_timeout(next_poll.saturating_duration_since(Instant::now())) {
+            Ok(SamplerCommand::SetRate(new_rate)) => {
+                rate = new_rate;
+                last_tick = None;
+                next_poll = Instant::now();
+                continue;
+            }
+            Ok(SamplerCommand::Resample) => {
+                resample = true;
+                next_poll = Instant::now();
+                continue;
+            }
+            Ok(SamplerCommand::StartRecording(new_recorder)) => {
+                if let Some(recorder) = recorder.replace(new_recorder) {
+                    stop_recording(recorder, None, &events);
+                }
+                continue;
+            }
+            Ok(SamplerCommand::StopRecording) => {
+                if let Some(recorder) = recorder.take() {
+                    stop_recording(recorder, None, &events);
+                }
+                continue;
+            }
+            Ok(SamplerCommand::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+
+        next_poll += rate.interval();
+        if next_poll < Instant::now() {
+            // We fell behind, do not try to catch up with a burst of samples.
+            next_poll = Instant::now() + rate.interval();
+        }
+
+        let mut memory = memory.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        memory.refresh();
+
+        let mut dropped_ticks = 0;
+        if rate == SampleRate::GameTick && !resample {
+            match read_local_time(&memory) {
+                Ok(tick) if Some(tick) == last_tick => continue,
+                Ok(tick) => {
+                    // local_time starts over with every map, only a jump forward means we missed ticks.
+                    if let Some(last_tick) = last_tick.filter(|last_tick| tick > *last_tick) {
+                        dropped_ticks = tick - last_tick - 1;
+                    }
+                    last_tick = Some(tick);
+                }
+                Err(_) => {
+                    last_tick = None;
+                    if last_sample.is_some_and(|last_sample| last_sample.elapsed() < NO_TICK_SAMPLE_INTERVAL) {
+                        continue;
+                    }
+                }
+            }
+        }
+        resample = false;
+
+        if !memory.is_alive() {
+            if let Some(recorder) = recorder.take() {
+                stop_recording(recorder, Some("xemu exited".to_string()), &events);
+            }
+            let _ = events.send(SamplerEvent::Exited);
+            break;
+        }
+
+        let snapshot = build_snapshot(&memory).map(Box::new);
+        let pages_read = memory.pages_read();
+
+        if let (Some(active_recorder), Ok(snapshot)) = (recorder.as_mut(), &snapshot) {
+            if let Err(e) = active_recorder.record(&memory, snapshot) {
+                stop_recording(recorder.take().unwrap(), Some(e.to_string()), &events);
+            }
+        }
+        drop(memory);
+
+        last_sample = Some(Instant::now());
+        if events.send(SamplerEvent::Sample { snapshot, pages_read, dropped_ticks }).is_err() {
+            break;
+        }
+    }
+
+    if let Some(recorder) = recorder {
+        let _ = recorder.finish();
+    }
+}
@@ -0,0 +1,208 @@
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+use rhai::{serde::to_dynamic, Array, Blob, Dynamic, Engine, EvalAltResult, FnPtr, FLOAT, INT};
+use serde::Serialize;
+
+use crate::{
+    engine::{object_type_string, Datum, EngineSnapshot, Object, ObjectHeaderEntry},
+    memory::{Memory, MemoryError}
+};
+
+use super::{
+    hooks::{Hook, ScriptAction, Trigger},
+    ScriptContext
+};
+
+pub(super) type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+// The snapshot a script runs against, shared so copying it around in scripts is cheap.
+#[derive(Clone)]
+pub struct ScriptSnapshot(pub Arc<EngineSnapshot>);
+
+#[derive(Serialize)]
+struct ScriptObject<'a> {
+    index: usize,
+    header: &'a ObjectHeaderEntry,
+    object: &'a Object,
+    object_type: &'a str,
+    tag: Option<&'a str>
+}
+
+fn dynamic<T: Serialize>(value: &T) -> ScriptResult<Dynamic> {
+    to_dynamic(value)
+}
+
+fn index(value: INT, length: usize) -> ScriptResult<usize> {
+    usize::try_from(value).ok()
+        .filter(|&index| index < length)
+        .ok_or_else(|| format!("index {} is outside 0..{}", value, length).into())
+}
+
+fn address(value: INT) -> ScriptResult<u32> {
+    u32::try_from(value).map_err(|_| format!("address {:#x} is outside the guest memory", value).into())
+}
+
+pub(super) fn object(snapshot: &EngineSnapshot, index: usize) -> ScriptResult<Dynamic> {
+    let Some((header, object)) = snapshot.slot(index) else {
+        return Ok(Dynamic::UNIT);
+    };
+
+    dynamic(&ScriptObject {
+        index,
+        header,
+        object,
+        object_type: object_type_string(header.data_type),
+        tag: snapshot.tags.get(&object.tag_index).map(String::as_str)
+    })
+}
+
+fn with_memory<T>(context: &RefCell<ScriptContext>, access: impl FnOnce(&mut Memory) -> Result<T, MemoryError>) -> ScriptResult<T> {
+    let context = context.borrow();
+    let Some(memory) = &context.memory else {
+        return Err("no memory to access, only attached sessions have memory".into());
+    };
+
+    let mut memory = memory.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    access(&mut memory).map_err(|e| e.to_string().into())
+}
+
+fn write_memory(context: &RefCell<ScriptContext>, address: u32, bytes: &[u8]) -> ScriptResult<()> {
+    with_memory(context, |memory| memory.write(address, bytes))?;
+    context.borrow_mut().wrote_memory = true;
+    Ok(())
+}
+
+// read_<name>(address) and write_<name>(address, value) for a value type in guest memory.
+macro_rules! register_memory_type {
+    ($engine:expr, $context:expr, $name:literal, $type:ty, $script_type:ty) => {{
+        let context = $context.clone();
+        $engine.register_fn(concat!("read_", $name), move |address: INT| -> ScriptResult<$script_type> {
+            let address = self::address(address)?;
+            with_memory(&context, |memory| memory.read::<$type>(address)).map(|value| value as $script_type)
+        });
+
+        let context = $context.clone();
+        $engine.register_fn(concat!("write_", $name), move |address: INT, value: $script_type| -> ScriptResult<()> {
+            write_memory(&context, self::address(address)?, &(value as $type).to_le_bytes())
+        });
+    }};
+}
+
+fn register_memory(engine: &mut Engine, context: &Rc<RefCell<ScriptContext>>) {
+    register_memory_type!(engine, context, "u8", u8, INT);
+    register_memory_type!(engine, context, "i8", i8, INT);
+    register_memory_type!(engine, context, "u16", u16, INT);
+    register_memory_type!(engine, context, "i16", i16, INT);
+    register_memory_type!(engine, context, "u32", u32, INT);
+    register_memory_type!(engine, context, "i32", i32, INT);
+    register_memory_type!(engine, context, "f32", f32, FLOAT);
+
+    let read_context = context.clone();
+    engine.register_fn("read_str", move |address: INT| -> ScriptResult<String> {
+        let address = self::address(address)?;
+        with_memory(&read_context, |memory| memory.read_str(address))
+    });
+
+    let write_context = context.clone();
+    engine.register_fn("write_bytes", move |address: INT, bytes: Blob| -> ScriptResult<()> {
+        write_memory(&write_context, self::address(address)?, &bytes)
+    });
+}
+
+fn register_datum(engine: &mut Engine) {
+    engine.register_type_with_name::<Datum>("Datum")
+        .register_fn("datum", |handle: INT| Datum::from_raw(handle as u32))
+        .register_fn("datum", |index: INT, id: INT| Datum::from_parts(index as u16, id as u16))
+        .register_get("index", |datum: &mut Datum| datum.get_index() as INT)
+        .register_get("id", |datum: &mut Datum| datum.get_id() as INT)
+        .register_get("handle", |datum: &mut Datum| datum.get_handle() as INT)
+        .register_fn("is_invalid", |datum: &mut Datum| datum.is_invalid())
+        .register_fn("==", |a: &mut Datum, b: Datum| *a == b)
+        .register_fn("!=", |a: &mut Datum, b: Datum| *a != b)
+        .register_fn("to_string", |datum: &mut Datum| format!("{:?}", datum))
+        .register_fn("to_debug", |datum: &mut Datum| format!("{:?}", datum));
+}
+
+fn register_snapshot(engine: &mut Engine) {
+    engine.register_type_with_name::<ScriptSnapshot>("Snapshot")
+        .register_get("map_name", |snapshot: &mut ScriptSnapshot| snapshot.0.map_name.clone())
+        .register_get("tick", |snapshot: &mut ScriptSnapshot| snapshot.0.game_time_globals.local_time as INT)
+        .register_get("first_free_index", |snapshot: &mut ScriptSnapshot| snapshot.0.first_free_index() as INT)
+        .register_get("object_header", |snapshot: &mut ScriptSnapshot| dynamic(&snapshot.0.object_header))
+        .register_get("player_header", |snapshot: &mut ScriptSnapshot| dynamic(&snapshot.0.player_header))
+        .register_get("player_globals", |snapshot: &mut ScriptSnapshot| dynamic(&snapshot.0.player_globals))
+        .register_get("game_globals", |snapshot: &mut ScriptSnapshot| dynamic(&snapshot.0.game_globals))
+        .register_get("game_time_globals", |snapshot: &mut ScriptSnapshot| dynamic(&snapshot.0.game_time_globals))
+        // Object maps of the filled slots, () for free ones.
+        .register_fn("object", |snapshot: &mut ScriptSnapshot, slot: INT| -> ScriptResult<Dynamic> {
+            object(&snapshot.0, index(slot, snapshot.0.object_entries.len())?)
+        })
+        .register_fn("objects", |snapshot: &mut ScriptSnapshot| -> ScriptResult<Array> {
+            (0..snapshot.0.object_entries.len())
+                .map(|slot| object(&snapshot.0, slot))
+                .filter(|object| !object.as_ref().is_ok_and(Dynamic::is_unit))
+                .collect()
+        })
+        .register_fn("player", |snapshot: &mut ScriptSnapshot, slot: INT| -> ScriptResult<Dynamic> {
+            match &snapshot.0.player_entries[index(slot, snapshot.0.player_entries.len())?] {
+                Some(player) => dynamic(player),
+                None => Ok(Dynamic::UNIT)
+            }
+        })
+        // The local player controlling the unit in `slot`, () if none does.
+        .register_fn("player_of", |snapshot: &mut ScriptSnapshot, slot: INT| {
+            snapshot.0.find_local_player_index_from_unit_index(slot as u16)
+                .map(|player| Dynamic::from(player as INT))
+                .unwrap_or(Dynamic::UNIT)
+        })
+        .register_fn("tag", |snapshot: &mut ScriptSnapshot, tag_index: INT| {
+            snapshot.0.tags.get(&(tag_index as u32))
+                .map(|path| Dynamic::from(path.clone()))
+                .unwrap_or(Dynamic::UNIT)
+        })
+        .register_fn("to_string", |snapshot: &mut ScriptSnapshot| {
+            format!("Snapshot({} tick {})", snapshot.0.map_name, snapshot.0.game_time_globals.local_time)
+        });
+}
+
+fn register_hook(engine: &mut Engine, context: &Rc<RefCell<ScriptContext>>, trigger: Trigger) {
+    let hook_context = context.clone();
+    engine.register_fn(trigger.function_name(), move |callback: FnPtr| {
+        hook_context.borrow_mut().hooks.push(Hook { trigger, callback });
+    });
+}
+
+// Hooks for a single slot, e.g. on_slot_freed(57, |index, object| stop_recording()).
+fn register_slot_hook(engine: &mut Engine, context: &Rc<RefCell<ScriptContext>>, trigger: fn(Option<usize>) -> Trigger) {
+    register_hook(engine, context, trigger(None));
+
+    let hook_context = context.clone();
+    engine.register_fn(trigger(None).function_name(), move |slot: INT, callback: FnPtr| -> ScriptResult<()> {
+        let slot = usize::try_from(slot).map_err(|_| format!("invalid slot {}", slot))?;
+        hook_context.borrow_mut().hooks.push(Hook { trigger: trigger(Some(slot)), callback });
+        Ok(())
+    });
+}
+
+fn register_hooks(engine: &mut Engine, context: &Rc<RefCell<ScriptContext>>) {
+    register_hook(engine, context, Trigger::Tick);
+    register_slot_hook(engine, context, Trigger::SlotFreed);
+    register_slot_hook(engine, context, Trigger::SlotFilled);
+    register_hook(engine, context, Trigger::RespawnFailureChanged);
+    register_hook(engine, context, Trigger::LocalDeadPlayersChanged);
+
+    let clear_context = context.clone();
+    engine.register_fn("clear_hooks", move || clear_context.borrow_mut().hooks.clear());
+
+    let action_context = context.clone();
+    engine.register_fn("stop_recording", move || action_context.borrow_mut().actions.push(ScriptAction::StopRecording));
+    let action_context = context.clone();
+    engine.register_fn("pause_replay", move || action_context.borrow_mut().actions.push(ScriptAction::PauseReplay));
+}
+
+pub(super) fn register(engine: &mut Engine, context: &Rc<RefCell<ScriptContext>>) {
+    register_memory(engine, context);
+    register_datum(engine);
+    register_snapshot(engine);
+    register_hooks(engine, context);
+}
@@ -0,0 +1,80 @@
+use rhai::{Dynamic, FnPtr, INT};
+
+use crate::engine::{EngineSnapshot, EventKind, SnapshotEvent, LOCAL_DEAD_PLAYER, RESPAWN_FAILURE};
+
+use super::bindings::{object, ScriptResult};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trigger {
+    Tick,
+    // Only for the given slot when set.
+    SlotFreed(Option<usize>),
+    SlotFilled(Option<usize>),
+    RespawnFailureChanged,
+    LocalDeadPlayersChanged
+}
+
+impl Trigger {
+    // The function a script registers the hook with.
+    pub fn function_name(&self) -> &'static str {
+        match self {
+            Trigger::Tick => "on_tick",
+            Trigger::SlotFreed(_) => "on_slot_freed",
+            Trigger::SlotFilled(_) => "on_slot_filled",
+            Trigger::RespawnFailureChanged => "on_respawn_failure_changed",
+            Trigger::LocalDeadPlayersChanged => "on_local_dead_players_changed"
+        }
+    }
+
+    fn matches(&self, kind: &EventKind) -> bool {
+        match (self, kind) {
+            (Trigger::SlotFreed(slot), EventKind::ObjectDeleted(object_summary)) | (Trigger::SlotFilled(slot), EventKind::ObjectCreated(object_summary)) => {
+                slot.is_none_or(|slot| slot == object_summary.index)
+            }
+            (Trigger::RespawnFailureChanged, EventKind::PlayerGlobalsChanged(change)) => change.name == RESPAWN_FAILURE,
+            (Trigger::LocalDeadPlayersChanged, EventKind::PlayerUnitChanged { field, .. }) => *field == LOCAL_DEAD_PLAYER,
+            _ => false
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Hook {
+    pub trigger: Trigger,
+    pub callback: FnPtr
+}
+
+// Things scripts ask of the session, carried out once the script returns.
+pub enum ScriptAction {
+    StopRecording,
+    PauseReplay
+}
+
+fn arguments(kind: &EventKind, before: &EngineSnapshot, after: &EngineSnapshot) -> ScriptResult<Vec<Dynamic>> {
+    Ok(match kind {
+        // A freed slot is passed the object as it was, a filled one the new object.
+        EventKind::ObjectDeleted(object_summary) => vec![Dynamic::from(object_summary.index as INT), object(before, object_summary.index)?],
+        EventKind::ObjectCreated(object_summary) => vec![Dynamic::from(object_summary.index as INT), object(after, object_summary.index)?],
+        // Respawn failure is the only players globals change a trigger matches.
+        EventKind::PlayerGlobalsChanged(_) => vec![
+            Dynamic::from(before.player_globals.respawn_failure as INT),
+            Dynamic::from(after.player_globals.respawn_failure as INT)
+        ],
+        EventKind::PlayerUnitChanged { player, before, after, .. } => vec![Dynamic::from(*player as INT), Dynamic::from(before.clone()), Dynamic::from(after.clone())]
+    })
+}
+
+// Every hook call due for `events` between `before` and `after`, along with its arguments. Tick hooks are left to the caller.
+pub fn due_calls(hooks: &[Hook], events: &[SnapshotEvent], before: &EngineSnapshot, after: &EngineSnapshot) -> ScriptResult<Vec<(usize, Vec<Dynamic>)>> {
+    let mut calls = Vec::new();
+
+    for event in events {
+        for (hook_index, hook) in hooks.iter().enumerate() {
+            if hook.trigger.matches(&event.kind) {
+                calls.push((hook_index, arguments(&event.kind, before, after)?));
+            }
+        }
+    }
+
+    Ok(calls)
+}
@@ -0,0 +1,214 @@
+mod bindings;
+mod hooks;
+
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{Arc, Mutex}
+};
+
+use rhai::{Dynamic, Engine, Scope, AST};
+
+use crate::{
+    engine::{EngineSnapshot, SnapshotEvent},
+    memory::Memory,
+    session::Session
+};
+
+use bindings::ScriptSnapshot;
+use hooks::{due_calls, Hook, ScriptAction, Trigger};
+
+// Stops a runaway script, e.g. an endless loop, from freezing the window.
+const MAX_OPERATIONS: u64 = 5_000_000;
+
+// What the bindings can reach while a script runs, set by bind and cleared by release.
+#[derive(Default)]
+struct ScriptContext {
+    memory: Option<Arc<Mutex<Memory>>>,
+    wrote_memory: bool,
+    output: Vec<String>,
+    hooks: Vec<Hook>,
+    actions: Vec<ScriptAction>
+}
+
+// Rhai scripts run against the snapshot and memory of a session. Variables and functions persist between runs.
+pub struct ScriptEngine {
+    engine: Engine,
+    scope: Scope<'static>,
+    // Functions defined by every script run so far.
+    functions: AST,
+    context: Rc<RefCell<ScriptContext>>,
+    // Snapshot set by bind.
+    bound: Option<ScriptSnapshot>,
+    // Hooks that failed since the last take_hook_errors.
+    hook_errors: Vec<String>
+}
+
+impl ScriptEngine {
+    pub fn new() -> ScriptEngine {
+        let context = Rc::new(RefCell::new(ScriptContext::default()));
+
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let print_context = context.clone();
+        engine.on_print(move |text| print_context.borrow_mut().output.push(text.to_string()));
+        let debug_context = context.clone();
+        engine.on_debug(move |text, _, position| debug_context.borrow_mut().output.push(format!("{} {}", position, text)));
+
+        bindings::register(&mut engine, &context);
+
+        ScriptEngine {
+            engine,
+            scope: Scope::new(),
+            functions: AST::empty(),
+            context,
+            bound: None,
+            hook_errors: Vec::new()
+        }
+    }
+
+    // Point `snapshot` and the memory functions at `session` until release.
+    pub fn bind(&mut self, session: Option<&Session>) {
+        self.bound = session.and_then(|session| session.snapshot.clone().map(ScriptSnapshot));
+
+        let snapshot = self.bound.as_ref().map(|snapshot| Dynamic::from(snapshot.clone())).unwrap_or(Dynamic::UNIT);
+        self.scope.set_or_push("snapshot", snapshot);
+
+        self.context.borrow_mut().memory = session.and_then(Session::shared_memory);
+    }
+
+    // Carry out the actions scripts asked for, and resample `session` when they wrote to its memory so the change shows up.
+    pub fn release(&mut self, mut session: Option<&mut Session>) {
+        let mut context = self.context.borrow_mut();
+        context.memory = None;
+
+        for action in std::mem::take(&mut context.actions) {
+            let message = match (action, session.as_deref_mut()) {
+                (_, None) => "No session to act on",
+                (ScriptAction::StopRecording, Some(session)) if session.recording.is_some() => {
+                    session.stop_recording();
+                    "Stopping recording"
+                }
+                (ScriptAction::StopRecording, Some(_)) => "Not recording",
+                (ScriptAction::PauseReplay, Some(session)) => match session.replay.as_mut() {
+                    Some(replay) => {
+                        replay.pause();
+                        "Paused replay"
+                    }
+                    None => "Not a replay"
+                }
+            };
+            context.output.push(message.to_string());
+        }
+
+        if std::mem::take(&mut context.wrote_memory) {
+            if let Some(session) = session {
+                session.resample();
+            }
+        }
+    }
+
+    pub fn has_hooks(&self) -> bool {
+        !self.context.borrow().hooks.is_empty()
+    }
+
+    pub fn hook_descriptions(&self) -> Vec<String> {
+        self.context.borrow().hooks.iter().map(|hook| match hook.trigger {
+            Trigger::SlotFreed(Some(slot)) | Trigger::SlotFilled(Some(slot)) => format!("{}({})", hook.trigger.function_name(), slot),
+            trigger => trigger.function_name().to_string()
+        }).collect()
+    }
+
+    pub fn clear_hooks(&mut self) {
+        self.context.borrow_mut().hooks.clear();
+    }
+
+    // Run the hooks due for the bound snapshot, `events` led up to it from `previous`.
+    // A hook that fails is removed, rather than failing again every tick.
+    pub fn run_hooks(&mut self, previous: Option<&EngineSnapshot>, events: &[SnapshotEvent]) {
+        let Some(current) = self.bound.clone() else {
+            return;
+        };
+
+        let hooks = self.context.borrow().hooks.clone();
+
+        // A resample, e.g. after a hook wrote memory, is the same tick again and would run tick hooks every frame.
+        let new_tick = previous.is_none_or(|previous| previous.game_time_globals.local_time != current.0.game_time_globals.local_time);
+
+        let mut calls: Vec<(usize, Vec<Dynamic>)> = hooks.iter().enumerate()
+            .filter(|(_, hook)| new_tick && hook.trigger == Trigger::Tick)
+            .map(|(hook_index, _)| (hook_index, vec![Dynamic::from(current.clone())]))
+            .collect();
+
+        if let Some(previous) = previous {
+            match due_calls(&hooks, events, previous, &current.0) {
+                Ok(due) => calls.extend(due),
+                Err(e) => self.hook_errors.push(e.to_string())
+            }
+        }
+
+        let mut failed = Vec::new();
+        for (hook_index, arguments) in calls {
+            if failed.contains(&hook_index) {
+                continue;
+            }
+
+            let hook = &hooks[hook_index];
+            if let Err(e) = hook.callback.call::<Dynamic>(&self.engine, &self.functions, arguments) {
+                self.hook_errors.push(format!("Removed {} hook: {}", hook.trigger.function_name(), e));
+                failed.push(hook_index);
+            }
+        }
+
+        // Hooks added by the ones that ran come after these, a clear_hooks leaves nothing to remove.
+        let mut context = self.context.borrow_mut();
+        if context.hooks.len() >= hooks.len() {
+            let mut hook_index = 0;
+            context.hooks.retain(|_| {
+                hook_index += 1;
+                !failed.contains(&(hook_index - 1))
+            });
+        }
+    }
+
+    // Why hooks failed since the last call.
+    pub fn take_hook_errors(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.hook_errors)
+    }
+
+    // Compile and run `script`, keeping any functions it defines.
+    pub fn run(&mut self, script: &str) -> Result<Dynamic, String> {
+        let ast = self.engine.compile_with_scope(&self.scope, script).map_err(|e| e.to_string())?;
+        self.functions += ast.clone_functions_only();
+
+        self.eval(&ast)
+    }
+
+    // Compile an expression to evaluate later, e.g. a watch.
+    pub fn compile_expression(&self, expression: &str) -> Result<AST, String> {
+        self.engine.compile_expression_with_scope(&self.scope, expression).map_err(|e| e.to_string())
+    }
+
+    pub fn eval(&mut self, ast: &AST) -> Result<Dynamic, String> {
+        let ast = self.functions.merge(ast);
+        self.engine.eval_ast_with_scope(&mut self.scope, &ast).map_err(|e| e.to_string())
+    }
+
+    // Lines printed by scripts since the last call.
+    pub fn take_output(&self) -> Vec<String> {
+        std::mem::take(&mut self.context.borrow_mut().output)
+    }
+
+    pub fn reset(&mut self) {
+        self.scope.clear();
+        self.functions = AST::empty();
+        self.clear_hooks();
+    }
+}
+
+impl Default for ScriptEngine {
+    fn default() -> ScriptEngine {
+        ScriptEngine::new()
+    }
+}
@@ -0,0 +1,285 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex
+    },
+    thread,
+    time::Duration
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tungstenite::{error::ProtocolError, Message, WebSocket};
+
+use crate::engine::{EngineSnapshot, Object, ObjectHeaderEntry};
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+// Snapshots a client has not been sent yet, a slow client skips the ones after this rather than holding up the viewer.
+const CLIENT_QUEUE: usize = 4;
+
+// How long a client waits for a request before checking for snapshots to send.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// A snapshot is only serialized once it reaches a client thread, the viewer just hands over a reference to it.
+#[derive(Clone)]
+struct Published {
+    session: usize,
+    name: String,
+    snapshot: Arc<EngineSnapshot>
+}
+
+struct Shared {
+    // Latest snapshot of every session, queries are answered from these.
+    sessions: Mutex<BTreeMap<usize, Published>>,
+    // Keyed by an ID per connection, so a client can remove itself when it goes away.
+    clients: Mutex<BTreeMap<usize, SyncSender<Published>>>,
+    // What went wrong on the server threads since the last take_errors.
+    errors: Mutex<Vec<String>>,
+    stopped: AtomicBool
+}
+
+impl Shared {
+    fn report(&self, error: String) {
+        self.errors.lock().unwrap().push(error);
+    }
+}
+
+// Publishes snapshots as JSON over WebSocket to clients on localhost and answers their queries.
+pub struct Server {
+    address: SocketAddr,
+    shared: Arc<Shared>
+}
+
+#[derive(Deserialize)]
+struct Request {
+    // Echoed back in the response so clients can match them up.
+    #[serde(default)]
+    id: Value,
+    // Defaults to the first session.
+    session: Option<usize>,
+    #[serde(flatten)]
+    query: Query
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "query", rename_all = "snake_case")]
+enum Query {
+    Sessions,
+    Snapshot,
+    Object { index: usize },
+    PlayerGlobals,
+    Tag { tag_index: u32 }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Reply<'a> {
+    Snapshot { session: usize, name: &'a str, snapshot: &'a EngineSnapshot },
+    Response { id: Value, result: Value },
+    Error { id: Value, error: String }
+}
+
+#[derive(Serialize)]
+struct SessionInfo<'a> {
+    session: usize,
+    name: &'a str,
+    map_name: &'a str,
+    tick: u32
+}
+
+#[derive(Serialize)]
+struct ObjectInfo<'a> {
+    index: usize,
+    header: Option<&'a ObjectHeaderEntry>,
+    object: Option<&'a Object>,
+    tag: Option<&'a str>
+}
+
+#[derive(Serialize)]
+struct TagInfo<'a> {
+    tag_index: u32,
+    path: &'a str
+}
+
+impl Server {
+    pub fn start(port: u16) -> io::Result<Server> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+
+        let shared = Arc::new(Shared {
+            sessions: Mutex::new(BTreeMap::new()),
+            clients: Mutex::new(BTreeMap::new()),
+            errors: Mutex::new(Vec::new()),
+            stopped: AtomicBool::new(false)
+        });
+
+        let server = Server { address: listener.local_addr()?, shared: shared.clone() };
+        thread::spawn(move || accept(listener, shared));
+        Ok(server)
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.shared.clients.lock().unwrap().len()
+    }
+
+    // Send a new snapshot of `session` to every client and answer queries from it until the next one.
+    pub fn publish(&self, session: usize, name: &str, snapshot: &Arc<EngineSnapshot>) {
+        let published = Published { session, name: name.to_string(), snapshot: snapshot.clone() };
+
+        self.shared.clients.lock().unwrap()
+            .retain(|_, client| !matches!(client.try_send(published.clone()), Err(TrySendError::Disconnected(_))));
+
+        self.shared.sessions.lock().unwrap().insert(session, published);
+    }
+
+    pub fn remove_session(&self, session: usize) {
+        self.shared.sessions.lock().unwrap().remove(&session);
+    }
+
+    // Why clients were dropped or snapshots could not be sent since the last call, for the caller to show.
+    pub fn take_errors(&self) -> Vec<String> {
+        std::mem::take(&mut self.shared.errors.lock().unwrap())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+fn accept(listener: TcpListener, shared: Arc<Shared>) {
+    let mut next_client = 0;
+    while !shared.stopped.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let client = next_client;
+                next_client += 1;
+
+                let (sender, receiver) = mpsc::sync_channel(CLIENT_QUEUE);
+                shared.clients.lock().unwrap().insert(client, sender);
+
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    if let Err(e) = serve_client(stream, receiver, &shared) {
+                        shared.report(format!("API client disconnected: {}", e));
+                    }
+                    shared.clients.lock().unwrap().remove(&client);
+                });
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL * 5),
+            Err(e) => {
+                shared.report(format!("API server stopped accepting clients: {}", e));
+                return;
+            }
+        }
+    }
+}
+
+fn serve_client(stream: TcpStream, snapshots: Receiver<Published>, shared: &Shared) -> Result<(), Box<tungstenite::Error>> {
+    stream.set_nonblocking(false).map_err(tungstenite::Error::Io)?;
+    let mut socket = tungstenite::accept(stream).map_err(|e| match e {
+        tungstenite::HandshakeError::Failure(e) => e,
+        tungstenite::HandshakeError::Interrupted(_) => tungstenite::Error::Io(io::ErrorKind::WouldBlock.into())
+    })?;
+    socket.get_mut().set_read_timeout(Some(POLL_INTERVAL)).map_err(tungstenite::Error::Io)?;
+
+    while !shared.stopped.load(Ordering::Relaxed) {
+        for published in snapshots.try_iter() {
+            let reply = Reply::Snapshot { session: published.session, name: &published.name, snapshot: &published.snapshot };
+            match serde_json::to_string(&reply) {
+                Ok(json) => socket.send(Message::text(json))?,
+                Err(e) => shared.report(format!("Could not serialize snapshot: {}", e))
+            }
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let reply = answer(&text, shared);
+                socket.send(Message::text(reply))?;
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e)) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {}
+            Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::Protocol(ProtocolError::ResetWithoutClosingHandshake)) => break,
+            Err(e) => return Err(e.into())
+        }
+    }
+
+    close(&mut socket);
+    Ok(())
+}
+
+fn close(socket: &mut WebSocket<TcpStream>) {
+    // The peer may already be gone, in which case there is nothing left to tell it.
+    let _ = socket.close(None);
+    let _ = socket.flush();
+}
+
+fn answer(text: &str, shared: &Shared) -> String {
+    // Only references are copied, so the viewer is not held up publishing while a large query is answered.
+    let sessions = shared.sessions.lock().unwrap().clone();
+
+    let reply = match serde_json::from_str::<Request>(text) {
+        Ok(request) => match query(&request, &sessions) {
+            Ok(result) => Reply::Response { id: request.id, result },
+            Err(error) => Reply::Error { id: request.id, error }
+        },
+        Err(e) => Reply::Error { id: Value::Null, error: format!("invalid request: {}", e) }
+    };
+
+    serde_json::to_string(&reply).unwrap_or_else(|e| format!(r#"{{"type":"error","id":null,"error":"{}"}}"#, e))
+}
+
+fn query(request: &Request, sessions: &BTreeMap<usize, Published>) -> Result<Value, String> {
+    let snapshot = || {
+        let published = match request.session {
+            Some(session) => sessions.get(&session),
+            None => sessions.values().next()
+        };
+        published.map(|published| &*published.snapshot).ok_or_else(|| "no snapshot for that session".to_string())
+    };
+
+    let result = match request.query {
+        Query::Sessions => {
+            let sessions: Vec<SessionInfo> = sessions.iter().map(|(session, published)| SessionInfo {
+                session: *session,
+                name: &published.name,
+                map_name: &published.snapshot.map_name,
+                tick: published.snapshot.game_time_globals.local_time
+            }).collect();
+            serde_json::to_value(sessions)
+        }
+        Query::Snapshot => serde_json::to_value(snapshot()?),
+        Query::Object { index } => {
+            let snapshot = snapshot()?;
+            if index >= snapshot.object_header.max_entries as usize {
+                return Err(format!("index {} is outside the object table of {} slots", index, snapshot.object_header.max_entries));
+            }
+
+            let object = snapshot.object_entries[index].as_ref();
+            serde_json::to_value(ObjectInfo {
+                index,
+                header: snapshot.object_header_entries[index].as_ref(),
+                object,
+                tag: object.and_then(|object| snapshot.tags.get(&object.tag_index)).map(String::as_str)
+            })
+        }
+        Query::PlayerGlobals => serde_json::to_value(&snapshot()?.player_globals),
+        Query::Tag { tag_index } => {
+            let path = snapshot()?.tags.get(&tag_index).ok_or_else(|| format!("no tag with index {}", tag_index))?;
+            serde_json::to_value(TagInfo { tag_index, path })
+        }
+    };
+
+    result.map_err(|e| e.to_string())
+}
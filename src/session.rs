@@ -0,0 +1,321 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    io,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::Instant
+};
+
+use crate::{
+    attach::{attempt_due, find_guest_memory, find_xemu_pids},
+    engine::{snapshot_events, EngineSnapshot, SnapshotError, SnapshotEvent},
+    memory::{save_dump, Memory, MemoryError},
+    recording::{Recorder, Replay},
+    sampler::{SampleRate, Sampler, SamplerEvent},
+    script::ScriptEngine
+};
+
+// Oldest events are dropped past this.
+const MAX_EVENTS: usize = 10000;
+
+// One attached emulator or opened dump, along with the latest snapshot its sampler built.
+pub struct Session {
+    pub id: usize,
+    pub name: String,
+    pub pid: Option<u32>,
+    pub waiting_for_xemu: bool,
+    // Shared with the API server and scripts, which keep it around after the next one comes in.
+    pub snapshot: Option<Arc<EngineSnapshot>>,
+    pub snapshot_error: Option<SnapshotError>,
+    pub pages_read: Option<usize>,
+    // Game ticks the sampler missed since attaching, only counted when sampling every game tick.
+    pub dropped_ticks: u64,
+    pub recording: Option<PathBuf>,
+    pub replay: Option<Replay>,
+    // Snapshot the diff view compares the current one against.
+    pub pinned: Option<Arc<EngineSnapshot>>,
+    pub target_index: u32,
+    // Whether the object inspector window is open for target_index.
+    pub inspecting: bool,
+    // Object and player transitions between consecutive snapshots, oldest first.
+    pub events: VecDeque<SnapshotEvent>,
+    // Runs the hooks scripts registered against every new snapshot of this session.
+    pub script_engine: Option<Rc<RefCell<ScriptEngine>>>,
+    // Latest snapshot that built, kept through failed ones so events and hooks still see what changed across them.
+    last_good: Option<Arc<EngineSnapshot>>,
+    // Whether `snapshot` was replaced since the last take_snapshot_changed.
+    snapshot_changed: bool,
+    sampler: Option<Sampler>,
+    sample_rate: SampleRate,
+    last_attach_attempt: Option<Instant>
+}
+
+impl Session {
+    pub fn new(id: usize, name: String, memory: Memory, pid: Option<u32>, sample_rate: SampleRate) -> Session {
+        Session {
+            sampler: Some(Sampler::spawn(memory, sample_rate)),
+            ..Session::empty(id, name, pid, sample_rate)
+        }
+    }
+
+    // Plays back a recording instead of sampling memory.
+    pub fn from_replay(id: usize, name: String, replay: Replay, sample_rate: SampleRate) -> Session {
+        let mut session = Session {
+            replay: Some(replay),
+            ..Session::empty(id, name, None, sample_rate)
+        };
+
+        session.show_replay_frame();
+        session
+    }
+
+    fn empty(id: usize, name: String, pid: Option<u32>, sample_rate: SampleRate) -> Session {
+        Session {
+            id,
+            name,
+            pid,
+            waiting_for_xemu: false,
+            snapshot: None,
+            snapshot_error: None,
+            pages_read: None,
+            dropped_ticks: 0,
+            recording: None,
+            replay: None,
+            pinned: None,
+            target_index: u32::MAX,
+            inspecting: false,
+            events: VecDeque::new(),
+            script_engine: None,
+            last_good: None,
+            snapshot_changed: false,
+            sampler: None,
+            sample_rate,
+            last_attach_attempt: None
+        }
+    }
+
+    fn set_snapshot(&mut self, snapshot: Result<EngineSnapshot, SnapshotError>) {
+        match snapshot {
+            Ok(snapshot) => {
+                let events = self.new_events(&snapshot);
+                self.events.extend(events.iter().cloned());
+                if self.events.len() > MAX_EVENTS {
+                    self.events.drain(..self.events.len() - MAX_EVENTS);
+                }
+
+                let snapshot = Arc::new(snapshot);
+                let previous = self.last_good.replace(snapshot.clone());
+                self.snapshot = Some(snapshot);
+                self.snapshot_error = None;
+                self.snapshot_changed = true;
+                self.run_hooks(previous.as_deref(), &events);
+            }
+            Err(e) => {
+                self.snapshot = None;
+                self.snapshot_error = Some(e);
+            }
+        }
+    }
+
+    // Only forward steps have events, seeking a replay back or across a map change would make them up.
+    fn new_events(&self, snapshot: &EngineSnapshot) -> Vec<SnapshotEvent> {
+        match &self.last_good {
+            Some(previous) if previous.map_name == snapshot.map_name && previous.game_time_globals.local_time < snapshot.game_time_globals.local_time => {
+                snapshot_events(previous, snapshot)
+            }
+            _ => Vec::new()
+        }
+    }
+
+    // Tick hooks run once per tick, event hooks only for what `events` saw happen since `previous`.
+    fn run_hooks(&mut self, previous: Option<&EngineSnapshot>, events: &[SnapshotEvent]) {
+        let Some(script_engine) = self.script_engine.clone() else { return; };
+        let mut script_engine = script_engine.borrow_mut();
+        if !script_engine.has_hooks() {
+            return;
+        }
+
+        script_engine.bind(Some(self));
+        script_engine.run_hooks(previous, events);
+        script_engine.release(Some(self));
+    }
+
+    // Whether a new snapshot came in since the last call.
+    pub fn take_snapshot_changed(&mut self) -> bool {
+        std::mem::take(&mut self.snapshot_changed)
+    }
+
+    fn show_replay_frame(&mut self) {
+        let Some(replay) = &self.replay else { return; };
+
+        let snapshot = replay.snapshot();
+        self.set_snapshot(snapshot);
+    }
+
+    // Jump the replay to `frame`, pausing it when `pause` is set.
+    pub fn seek_replay(&mut self, frame: usize, pause: bool) -> io::Result<()> {
+        let Some(replay) = self.replay.as_mut() else { return Ok(()); };
+
+        if pause {
+            replay.pause();
+        }
+
+        let result = replay.seek(frame);
+        self.show_replay_frame();
+        result
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: SampleRate) {
+        self.sample_rate = sample_rate;
+        self.dropped_ticks = 0;
+        if let Some(sampler) = &self.sampler {
+            sampler.set_rate(sample_rate);
+        }
+    }
+
+    // Start recording to `path`, appending the Objects table of every tick to `objects_csv` as well when given.
+    pub fn start_recording(&mut self, path: impl AsRef<Path>, objects_csv: Option<&str>) -> io::Result<()> {
+        let Some(sampler) = &self.sampler else {
+            return Err(io::Error::new(io::ErrorKind::NotConnected, "not attached"));
+        };
+
+        // Only pages read from a live emulator are tracked, a dump never changes anyway.
+        if sampler.memory().pages_read().is_none() {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "only live emulators can be recorded"));
+        }
+
+        let mut recorder = Recorder::create(path.as_ref())?;
+        if let Some(objects_csv) = objects_csv {
+            recorder.append_objects_csv(objects_csv)?;
+        }
+
+        sampler.start_recording(recorder);
+        self.recording = Some(path.as_ref().to_path_buf());
+        Ok(())
+    }
+
+    pub fn stop_recording(&mut self) {
+        if let Some(sampler) = &self.sampler {
+            sampler.stop_recording();
+        }
+    }
+
+    // Pick up the latest snapshot from the sampler. `other_pids` are attached to other sessions and never reattached to.
+    // Returns messages for the user, e.g. when the process behind this session went away.
+    pub fn update(&mut self, other_pids: &[u32]) -> Vec<String> {
+        let mut messages = Vec::new();
+        let mut exited = false;
+
+        if let Some(replay) = self.replay.as_mut() {
+            match replay.update() {
+                Ok(true) => self.show_replay_frame(),
+                Ok(false) => {}
+                Err(e) => {
+                    replay.pause();
+                    messages.push(format!("Could not play back {}: {}", self.name, e));
+                }
+            }
+        }
+
+        // Every snapshot goes through set_snapshot so events and hooks see each tick, only the last one is drawn.
+        let events: Vec<SamplerEvent> = self.sampler.as_ref().map(|sampler| sampler.events().collect()).unwrap_or_default();
+        for event in events {
+            match event {
+                SamplerEvent::Sample { snapshot, pages_read, dropped_ticks } => {
+                    self.pages_read = pages_read;
+                    self.dropped_ticks += dropped_ticks as u64;
+                    self.set_snapshot(snapshot.map(|snapshot| *snapshot));
+                }
+                SamplerEvent::RecordingStopped { path, frames, error } => {
+                    if self.recording.as_ref() == Some(&path) {
+                        self.recording = None;
+                    }
+                    messages.push(match error {
+                        Some(error) => format!("Recording to {} stopped after {} ticks: {}", path.display(), frames, error),
+                        None => format!("Recorded {} ticks to {}", frames, path.display())
+                    });
+                }
+                SamplerEvent::Exited => exited = true
+            }
+        }
+
+        if exited {
+            self.sampler = None;
+            self.snapshot = None;
+            self.last_good = None;
+            self.snapshot_error = None;
+            self.pages_read = None;
+            self.waiting_for_xemu = true;
+            self.last_attach_attempt = None;
+            messages.push(format!("Lost {}, waiting for xemu to restart", self.name));
+        }
+
+        if self.waiting_for_xemu {
+            self.reattach(other_pids);
+        }
+
+        messages
+    }
+
+    // Only reattach when exactly one unclaimed xemu is running, otherwise we cannot tell which one replaced ours.
+    fn reattach(&mut self, other_pids: &[u32]) {
+        if !attempt_due(&mut self.last_attach_attempt) {
+            return;
+        }
+
+        let candidates: Vec<u32> = find_xemu_pids().into_iter()
+            .filter(|pid| !other_pids.contains(pid))
+            .collect();
+
+        if let [pid] = candidates[..] {
+            if let Some((_, memory)) = find_guest_memory(pid) {
+                self.sampler = Some(Sampler::spawn(memory, self.sample_rate));
+                self.pid = Some(pid);
+                self.waiting_for_xemu = false;
+            }
+        }
+    }
+
+    pub fn write_memory(&mut self, physical_address: u32, bytes: &[u8]) -> Result<(), MemoryError> {
+        match &self.sampler {
+            Some(sampler) => {
+                sampler.memory().write(physical_address, bytes)?;
+
+                // A paused game does not tick, so ask for a snapshot to show the edit.
+                sampler.resample();
+                Ok(())
+            }
+            None => Err(MemoryError::WriteFailed {
+                address: physical_address,
+                length: bytes.len(),
+                reason: if self.replay.is_some() { "replays are read only" } else { "not attached" }.to_string()
+            })
+        }
+    }
+
+    // The memory of a live session, replays only have their snapshots.
+    pub fn shared_memory(&self) -> Option<Arc<Mutex<Memory>>> {
+        self.sampler.as_ref().map(Sampler::shared_memory)
+    }
+
+    // Ask for a snapshot to show writes made through shared_memory.
+    pub fn resample(&self) {
+        if let Some(sampler) = &self.sampler {
+            sampler.resample();
+        }
+    }
+
+    pub fn save_dump(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let Some(sampler) = &self.sampler else {
+            return Err(io::Error::new(io::ErrorKind::NotConnected, "not attached"));
+        };
+
+        let (map_name, local_time) = self.snapshot.as_ref()
+            .map(|snapshot| (snapshot.map_name.as_str(), snapshot.game_time_globals.local_time))
+            .unwrap_or_default();
+
+        save_dump(&sampler.memory(), path, map_name, local_time)
+    }
+}
@@ -0,0 +1,289 @@
+use std::{cell::RefCell, rc::Rc};
+
+use imgui::{Condition, FocusedWidget, StyleColor, TableFlags, TreeNodeFlags, Ui};
+use rhai::{Dynamic, AST};
+
+use crate::{script::ScriptEngine, session::Session, GREEN, RED, WHITE};
+
+// Older lines are dropped past this, a chatty script could otherwise grow the log forever.
+const MAX_OUTPUT_LINES: usize = 1000;
+
+enum OutputKind {
+    Input,
+    Output,
+    Error
+}
+
+struct Watch {
+    expression: String,
+    ast: AST,
+    value: Result<Dynamic, String>
+}
+
+// Runs Rhai scripts against the active session. Watched expressions are evaluated on every snapshot of it,
+// the hooks scripts register on every snapshot of every session.
+#[derive(Default)]
+pub struct ScriptConsole {
+    pub open: bool,
+    engine: Rc<RefCell<ScriptEngine>>,
+    input: String,
+    output: Vec<(OutputKind, String)>,
+    scroll_to_bottom: bool,
+    new_watch: String,
+    new_watch_error: Option<String>,
+    watches: Vec<Watch>
+}
+
+fn format_value(value: &Dynamic) -> String {
+    if value.is_string() {
+        format!("{:?}", value.to_string())
+    } else {
+        value.to_string()
+    }
+}
+
+impl ScriptConsole {
+    fn push_output(&mut self, kind: OutputKind, text: String) {
+        self.output.push((kind, text));
+        if self.output.len() > MAX_OUTPUT_LINES {
+            self.output.drain(..self.output.len() - MAX_OUTPUT_LINES);
+        }
+        self.scroll_to_bottom = true;
+    }
+
+    // The engine sessions run the hooks with.
+    pub fn engine(&self) -> Rc<RefCell<ScriptEngine>> {
+        self.engine.clone()
+    }
+
+    fn collect_printed(&mut self) {
+        let (lines, errors) = {
+            let mut engine = self.engine.borrow_mut();
+            (engine.take_output(), engine.take_hook_errors())
+        };
+
+        for line in lines {
+            self.push_output(OutputKind::Output, line);
+        }
+        for error in errors {
+            self.push_output(OutputKind::Error, error);
+        }
+    }
+
+    // Re-evaluate the watches against the latest snapshot of `session`.
+    pub fn update(&mut self, session: &mut Session) {
+        if self.watches.is_empty() {
+            return;
+        }
+
+        let mut engine = self.engine.borrow_mut();
+        engine.bind(Some(session));
+        for watch in self.watches.iter_mut() {
+            watch.value = engine.eval(&watch.ast);
+        }
+        engine.release(Some(session));
+        drop(engine);
+
+        self.collect_printed();
+    }
+
+    fn run_input(&mut self, session: Option<&mut Session>) {
+        let script = std::mem::take(&mut self.input);
+        if script.trim().is_empty() {
+            return;
+        }
+
+        for line in script.lines() {
+            self.push_output(OutputKind::Input, format!("> {}", line));
+        }
+
+        let result = {
+            let mut engine = self.engine.borrow_mut();
+            engine.bind(session.as_deref());
+            let result = engine.run(&script);
+            engine.release(session);
+            result
+        };
+        self.collect_printed();
+
+        match result {
+            Ok(value) if value.is_unit() => {}
+            Ok(value) => self.push_output(OutputKind::Output, format_value(&value)),
+            Err(e) => {
+                self.push_output(OutputKind::Error, e);
+                // Keep the script around to fix it.
+                self.input = script;
+            }
+        }
+    }
+
+    fn add_watch(&mut self, session: Option<&mut Session>) {
+        let expression = self.new_watch.trim().to_string();
+        if expression.is_empty() {
+            return;
+        }
+
+        let compiled = self.engine.borrow().compile_expression(&expression);
+        match compiled {
+            Ok(ast) => {
+                let value = {
+                    let mut engine = self.engine.borrow_mut();
+                    engine.bind(session.as_deref());
+                    let value = engine.eval(&ast);
+                    engine.release(session);
+                    value
+                };
+                self.collect_printed();
+
+                self.watches.push(Watch { expression, ast, value });
+                self.new_watch.clear();
+                self.new_watch_error = None;
+            }
+            Err(e) => self.new_watch_error = Some(e)
+        }
+    }
+
+    fn draw_watches(&mut self, ui: &Ui, session: Option<&mut Session>) {
+        let mut remove = None;
+
+        if !self.watches.is_empty() {
+            if let Some(table) = ui.begin_table_with_flags("Watches", 3, TableFlags::SIZING_STRETCH_PROP | TableFlags::ROW_BG | TableFlags::BORDERS_INNER_V) {
+                ui.table_setup_column("Expression");
+                ui.table_setup_column("Value");
+                ui.table_setup_column("");
+                ui.table_headers_row();
+
+                for (index, watch) in self.watches.iter().enumerate() {
+                    ui.table_next_row();
+                    ui.table_set_column_index(0);
+                    ui.text(&watch.expression);
+                    ui.table_next_column();
+                    match &watch.value {
+                        // Boolean watches read as conditions.
+                        Ok(value) if value.is_bool() => ui.text_colored(if value.as_bool().unwrap_or_default() { GREEN } else { RED }, value.to_string()),
+                        Ok(value) => ui.text(format_value(value)),
+                        Err(e) => ui.text_colored(RED, e)
+                    }
+                    ui.table_next_column();
+                    if ui.small_button(format!("Remove##{}", index)) {
+                        remove = Some(index);
+                    }
+                }
+
+                table.end();
+            }
+        }
+
+        if let Some(index) = remove {
+            self.watches.remove(index);
+        }
+
+        let add = ui.input_text("##New Watch", &mut self.new_watch)
+            .hint("Expression, e.g. snapshot.object_header.size")
+            .enter_returns_true(true)
+            .build();
+        ui.same_line();
+        if ui.button("Add Watch") || add {
+            self.add_watch(session);
+        }
+
+        if let Some(new_watch_error) = &self.new_watch_error {
+            ui.text_colored(RED, new_watch_error);
+        }
+    }
+
+    fn draw_hooks(&mut self, ui: &Ui) {
+        let hooks = self.engine.borrow().hook_descriptions();
+        if hooks.is_empty() {
+            ui.text("No hooks, register one with e.g. on_slot_freed(57, |index, object| stop_recording())");
+            return;
+        }
+
+        for hook in &hooks {
+            ui.bullet_text(hook);
+        }
+
+        if ui.button("Clear Hooks") {
+            self.engine.borrow_mut().clear_hooks();
+        }
+    }
+
+    pub fn draw(&mut self, ui: &Ui, mut session: Option<&mut Session>) {
+        // Hooks print and fail outside of the console, pick that up even while it is closed.
+        self.collect_printed();
+
+        if !self.open {
+            return;
+        }
+
+        let mut opened = true;
+        if let Some(console_window) = ui.window("Script Console")
+            .opened(&mut opened)
+            .size([700.0, 500.0], Condition::FirstUseEver)
+            .position([250.0, 150.0], Condition::FirstUseEver)
+            .begin()
+        {
+            match &session {
+                Some(session) => ui.text(format!("Running against {}", session.name)),
+                None => ui.text_colored(RED, "No session, snapshot is () and memory cannot be read")
+            }
+
+            if ui.collapsing_header("Watches", TreeNodeFlags::DEFAULT_OPEN) {
+                self.draw_watches(ui, session.as_deref_mut());
+            }
+
+            if ui.collapsing_header("Hooks", TreeNodeFlags::DEFAULT_OPEN) {
+                self.draw_hooks(ui);
+            }
+
+            ui.separator();
+
+            let input_height = ui.text_line_height_with_spacing() * 5.0;
+            let output_height = (ui.content_region_avail()[1] - input_height - ui.frame_height_with_spacing() * 2.0).max(50.0);
+            // Echoed input is muted but still has to read on the dark window background.
+            let input_color = ui.style_color(StyleColor::TextDisabled);
+            ui.child_window("Output").size([0.0, output_height]).border(true).build(|| {
+                for (kind, line) in &self.output {
+                    let color = match kind {
+                        OutputKind::Input => input_color,
+                        OutputKind::Output => WHITE,
+                        OutputKind::Error => RED
+                    };
+                    ui.text_colored(color, line);
+                }
+
+                if std::mem::take(&mut self.scroll_to_bottom) {
+                    ui.set_scroll_here_y_with_ratio(1.0);
+                }
+            });
+
+            // Enter adds a line, Ctrl+Enter runs the script.
+            let run = ui.input_text_multiline("##Script", &mut self.input, [-1.0, input_height])
+                .enter_returns_true(true)
+                .allow_tab_input(true)
+                .build();
+            if run {
+                ui.set_keyboard_focus_here_with_offset(FocusedWidget::Previous);
+            }
+
+            if ui.button("Run") || run {
+                self.run_input(session);
+            }
+            ui.same_line();
+            if ui.button("Clear Output") {
+                self.output.clear();
+            }
+            ui.same_line();
+            if ui.button("Reset") {
+                self.engine.borrow_mut().reset();
+                self.push_output(OutputKind::Output, "Cleared all variables and functions".to_string());
+            }
+
+            console_window.end();
+        }
+
+        if !opened {
+            self.open = false;
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use imgui::{Condition, TableFlags, Ui};
+
+use crate::{
+    engine::{diff_snapshots, FieldChange, SlotChange, SlotDiff},
+    session::Session,
+    GREEN, ORANGE, RED, WHITE
+};
+
+// Compares the current snapshot of a session against one pinned earlier, live or from a replay.
+#[derive(Default)]
+pub struct DiffWindow {
+    pub open: bool,
+    hide_moved: bool
+}
+
+fn draw_field_changes(ui: &Ui, id: &str, changes: &[FieldChange]) {
+    if changes.is_empty() {
+        ui.text("No changes");
+        return;
+    }
+
+    if let Some(table) = ui.begin_table_with_flags(id, 3, TableFlags::SIZING_STRETCH_PROP | TableFlags::ROW_BG) {
+        ui.table_setup_column("Field");
+        ui.table_setup_column("Pinned");
+        ui.table_setup_column("Current");
+        ui.table_headers_row();
+
+        for change in changes {
+            ui.table_next_row();
+            ui.table_set_column_index(0);
+            ui.text(change.name);
+            ui.table_next_column();
+            ui.text(&change.before);
+            ui.table_next_column();
+            ui.text_colored(ORANGE, &change.after);
+        }
+
+        table.end();
+    }
+}
+
+fn format_position(position: &[f32; 3]) -> String {
+    format!("{:.3} {:.3} {:.3}", position[0], position[1], position[2])
+}
+
+impl DiffWindow {
+    pub fn draw(&mut self, ui: &Ui, session: &mut Session) {
+        if !self.open {
+            return;
+        }
+
+        let mut opened = true;
+        if let Some(diff_window) = ui.window(format!("Diff - {}###Diff", session.name))
+            .opened(&mut opened)
+            .size([700.0, 500.0], Condition::FirstUseEver)
+            .position([150.0, 120.0], Condition::FirstUseEver)
+            .begin()
+        {
+            if ui.button("Pin Current") {
+                session.pinned = session.snapshot.clone();
+            }
+            ui.same_line();
+            if ui.button("Unpin") {
+                session.pinned = None;
+            }
+            ui.same_line();
+            ui.checkbox("Hide Moved Objects", &mut self.hide_moved);
+
+            match (&session.pinned, &session.snapshot) {
+                (None, _) => ui.text("Pin a snapshot to compare the current one against it."),
+                (Some(_), None) => ui.text_colored(RED, "Nothing to compare against, there is no current snapshot."),
+                (Some(pinned), Some(current)) => {
+                    ui.text(format!(
+                        "Pinned tick {} ({}) vs current tick {} ({})",
+                        pinned.game_time_globals.local_time,
+                        pinned.map_name,
+                        current.game_time_globals.local_time,
+                        current.map_name
+                    ));
+
+                    let diff = diff_snapshots(pinned, current);
+                    if diff.is_empty() {
+                        ui.text_colored(GREEN, "Nothing changed");
+                    }
+
+                    ui.separator();
+                    ui.text_colored(ORANGE, "Object Header");
+                    draw_field_changes(ui, "ObjectHeaderDiff", &diff.object_header);
+
+                    ui.separator();
+                    ui.text_colored(ORANGE, "Players Globals");
+                    draw_field_changes(ui, "PlayersGlobalsDiff", &diff.player_globals);
+
+                    ui.separator();
+                    ui.text_colored(ORANGE, "Objects");
+                    self.draw_slot_changes(ui, &diff.slots);
+                }
+            }
+
+            diff_window.end();
+        }
+
+        if !opened {
+            self.open = false;
+        }
+    }
+
+    fn draw_slot_changes(&self, ui: &Ui, slots: &[SlotDiff]) {
+        let slots: Vec<_> = slots.iter()
+            .filter(|slot| !(self.hide_moved && matches!(slot.change, SlotChange::Moved { .. })))
+            .collect();
+
+        if slots.is_empty() {
+            ui.text("No changes");
+            return;
+        }
+
+        if let Some(table) = ui.begin_table_with_flags("ObjectsDiff", 4, TableFlags::SIZING_STRETCH_PROP | TableFlags::ROW_BG) {
+            ui.table_setup_column("Index");
+            ui.table_setup_column("Change");
+            ui.table_setup_column("Pinned");
+            ui.table_setup_column("Current");
+            ui.table_headers_row();
+
+            for slot in slots {
+                ui.table_next_row();
+                ui.table_set_column_index(0);
+                ui.text(format!("{}", slot.index));
+
+                let (color, change, before, after) = match &slot.change {
+                    SlotChange::Freed { id, tag } => (RED, "Freed", format!("{} {}", id, tag), "Free".to_string()),
+                    SlotChange::Filled { id, tag } => (GREEN, "Filled", "Free".to_string(), format!("{} {}", id, tag)),
+                    SlotChange::IdChanged { before, after } => (ORANGE, "ID", before.to_string(), after.to_string()),
+                    SlotChange::TagChanged { before, after } => (ORANGE, "Tag", before.clone(), after.clone()),
+                    SlotChange::Moved { before, after } => (WHITE, "Moved", format_position(before), format_position(after))
+                };
+
+                ui.table_next_column();
+                ui.text_colored(color, change);
+                ui.table_next_column();
+                ui.text(before);
+                ui.table_next_column();
+                ui.text(after);
+            }
+
+            table.end();
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use imgui::{Condition, ListClipper, TableFlags, Ui};
+
+use crate::{
+    engine::{Datum, EventKind, SnapshotEvent},
+    session::Session,
+    GREEN, ORANGE, RED, WHITE
+};
+
+// Logs what changed from one snapshot to the next, so transitions that flash by in the tables can be looked at later.
+pub struct EventsWindow {
+    pub open: bool,
+    show_objects: bool,
+    show_players: bool,
+    show_globals: bool,
+    filter: String,
+    auto_scroll: bool
+}
+
+impl Default for EventsWindow {
+    fn default() -> EventsWindow {
+        EventsWindow {
+            open: false,
+            show_objects: true,
+            show_players: true,
+            show_globals: true,
+            filter: String::default(),
+            auto_scroll: true
+        }
+    }
+}
+
+fn format_datum(datum: &Datum) -> String {
+    if datum.is_invalid() {
+        "None".to_string()
+    } else {
+        format!("{} ({})", datum.get_index(), datum.get_id())
+    }
+}
+
+// Label, colour, slot and details columns of an event.
+fn describe(event: &SnapshotEvent) -> (String, [f32; 4], String, String) {
+    match &event.kind {
+        EventKind::ObjectCreated(object) | EventKind::ObjectDeleted(object) => {
+            let (label, color) = match event.kind {
+                EventKind::ObjectCreated(_) => ("Created", GREEN),
+                _ => ("Deleted", RED)
+            };
+            let details = format!(
+                "{} {} at {:.3} {:.3} {:.3}",
+                object.object_type,
+                object.tag,
+                object.position[0],
+                object.position[1],
+                object.position[2]
+            );
+            (label.to_string(), color, format!("{} ({})", object.index, object.id), details)
+        }
+        EventKind::PlayerUnitChanged { player, field, before, after } => (
+            format!("Player {} {}", player, field),
+            ORANGE,
+            String::default(),
+            format!("{} -> {}", format_datum(before), format_datum(after))
+        ),
+        EventKind::PlayerGlobalsChanged(change) => (
+            change.name.to_string(),
+            WHITE,
+            String::default(),
+            format!("{} -> {}", change.before, change.after)
+        )
+    }
+}
+
+impl EventsWindow {
+    fn is_shown(&self, event: &SnapshotEvent) -> bool {
+        match event.kind {
+            EventKind::ObjectCreated(_) | EventKind::ObjectDeleted(_) => self.show_objects,
+            EventKind::PlayerUnitChanged { .. } => self.show_players,
+            EventKind::PlayerGlobalsChanged(_) => self.show_globals
+        }
+    }
+
+    pub fn draw(&mut self, ui: &Ui, session: &mut Session) {
+        if !self.open {
+            return;
+        }
+
+        let mut opened = true;
+        if let Some(events_window) = ui.window(format!("Events - {}###Events", session.name))
+            .opened(&mut opened)
+            .size([800.0, 400.0], Condition::FirstUseEver)
+            .position([180.0, 140.0], Condition::FirstUseEver)
+            .begin()
+        {
+            ui.checkbox("Objects", &mut self.show_objects);
+            ui.same_line();
+            ui.checkbox("Players", &mut self.show_players);
+            ui.same_line();
+            ui.checkbox("Players Globals", &mut self.show_globals);
+            ui.same_line();
+            ui.checkbox("Auto Scroll", &mut self.auto_scroll);
+            ui.same_line();
+            if ui.button("Clear") {
+                session.events.clear();
+            }
+
+            ui.input_text("Filter", &mut self.filter)
+                .hint("Part of a tag path, object type or field name")
+                .build();
+
+            let filter = self.filter.to_lowercase();
+            let rows: Vec<_> = session.events.iter()
+                .filter(|event| self.is_shown(event))
+                .map(|event| (event.tick, describe(event)))
+                .filter(|(_, (label, _, _, details))| {
+                    filter.is_empty() || label.to_lowercase().contains(&filter) || details.to_lowercase().contains(&filter)
+                })
+                .collect();
+
+            ui.text(format!("Showing {} of {} events", rows.len(), session.events.len()));
+
+            let flags = TableFlags::SIZING_STRETCH_PROP | TableFlags::ROW_BG | TableFlags::SCROLL_Y | TableFlags::BORDERS_INNER_V;
+            if let Some(table) = ui.begin_table_with_flags("EventsTable", 4, flags) {
+                ui.table_setup_scroll_freeze(0, 1);
+                ui.table_setup_column("Tick");
+                ui.table_setup_column("Event");
+                ui.table_setup_column("Slot");
+                ui.table_setup_column("Details");
+                ui.table_headers_row();
+
+                // Only the rows in view are drawn, the log can hold thousands.
+                let mut clipper = ListClipper::new(rows.len() as i32).begin(ui);
+                while clipper.step() {
+                    for (tick, (label, color, slot, details)) in &rows[clipper.display_start() as usize..clipper.display_end() as usize] {
+                        ui.table_next_row();
+                        ui.table_set_column_index(0);
+                        ui.text(tick.to_string());
+                        ui.table_next_column();
+                        ui.text_colored(*color, label);
+                        ui.table_next_column();
+                        ui.text(slot);
+                        ui.table_next_column();
+                        ui.text(details);
+                    }
+                }
+
+                if self.auto_scroll && ui.scroll_y() >= ui.scroll_max_y() - 1.0 {
+                    ui.set_scroll_here_y_with_ratio(1.0);
+                }
+
+                table.end();
+            }
+
+            events_window.end();
+        }
+
+        if !opened {
+            self.open = false;
+        }
+    }
+}
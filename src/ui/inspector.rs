@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use imgui::{Condition, TableFlags, TreeNodeFlags, Ui};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    engine::{object_type_string, Datum},
+    session::Session,
+    ORANGE, RED
+};
+
+// Fields stay highlighted for this many ticks after they change.
+const CHANGE_HIGHLIGHT_TICKS: u32 = 30;
+
+// What the unk_ fields in front of the Object struct are believed to be, going by their offsets.
+const FIELD_HINTS: &[(&str, &str)] = &[
+    ("unk_24", "velocity"),
+    ("unk_36", "forward"),
+    ("unk_48", "up"),
+    ("unk_60", "angular velocity")
+];
+
+// The slot an inspector window last showed, to tell which fields changed since.
+#[derive(Default)]
+struct InspectedSlot {
+    index: usize,
+    id: Option<u16>,
+    values: HashMap<String, String>,
+    changed_at: HashMap<String, u32>
+}
+
+// Shows every field of the object in the target slot of each session that asked for it, updating with every snapshot.
+#[derive(Default)]
+pub struct ObjectInspector {
+    slots: HashMap<usize, InspectedSlot>
+}
+
+fn format_number(number: &serde_json::Number) -> String {
+    match (number.as_u64(), number.as_f64()) {
+        (Some(value), _) if value >= 10 => format!("{} ({:#x})", value, value),
+        (Some(value), _) => value.to_string(),
+        // Fields are f32 at most, printing them as f64 only adds noise.
+        (None, Some(value)) if !number.is_i64() => (value as f32).to_string(),
+        _ => number.to_string()
+    }
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Number(number) => format_number(number),
+        // Integer arrays are all byte arrays in the engine structs.
+        Value::Array(items) if items.iter().all(|item| item.is_u64()) => {
+            items.iter().map(|item| format!("{:02x}", item.as_u64().unwrap_or_default())).collect::<Vec<_>>().join(" ")
+        }
+        Value::Array(items) => items.iter().map(format_value).collect::<Vec<_>>().join(" "),
+        Value::Object(fields) if fields.contains_key("handle") => {
+            let datum = Datum::from_raw(fields["handle"].as_u64().unwrap_or(u32::MAX as u64) as u32);
+            if datum.is_invalid() {
+                "None".to_string()
+            } else {
+                format!("Index {} ID {} ({:#010x})", datum.get_index(), datum.get_id(), datum.get_handle())
+            }
+        }
+        Value::String(text) => text.clone(),
+        value => value.to_string()
+    }
+}
+
+// Field names and formatted values of a struct, in declaration order.
+fn fields<T: Serialize>(value: &T) -> Vec<(String, String)> {
+    let Ok(Value::Object(fields)) = serde_json::to_value(value) else {
+        return Vec::new();
+    };
+
+    fields.iter().map(|(name, value)| {
+        let formatted = match (name.as_str(), value.as_u64()) {
+            ("flags", Some(flags)) => {
+                let bits: Vec<String> = (0..32).filter(|bit| flags & (1 << bit) != 0).map(|bit| bit.to_string()).collect();
+                format!("{:#010x} (bits {})", flags, if bits.is_empty() { "none".to_string() } else { bits.join(" ") })
+            }
+            _ => format_value(value)
+        };
+
+        let label = match FIELD_HINTS.iter().find(|(field, _)| field == name) {
+            Some((_, hint)) => format!("{} ({})", name, hint),
+            None => name.clone()
+        };
+
+        (label, formatted)
+    }).collect()
+}
+
+impl InspectedSlot {
+    // Note which of `fields` differ from the last time they were seen at `tick`.
+    fn track(&mut self, section: &str, fields: &[(String, String)], tick: u32) {
+        for (name, value) in fields {
+            let key = format!("{}.{}", section, name);
+            if let Some(previous) = self.values.insert(key.clone(), value.clone()) {
+                if previous != *value {
+                    self.changed_at.insert(key, tick);
+                }
+            }
+        }
+    }
+
+    fn is_recently_changed(&self, section: &str, name: &str, tick: u32) -> bool {
+        self.changed_at.get(&format!("{}.{}", section, name))
+            .is_some_and(|changed_at| tick.saturating_sub(*changed_at) < CHANGE_HIGHLIGHT_TICKS)
+    }
+}
+
+fn draw_fields(ui: &Ui, section: &str, fields: &[(String, String)], slot: &InspectedSlot, tick: u32) {
+    if let Some(table) = ui.begin_table_with_flags(section, 2, TableFlags::SIZING_STRETCH_PROP | TableFlags::ROW_BG | TableFlags::BORDERS_INNER_V) {
+        ui.table_setup_column("Field");
+        ui.table_setup_column("Value");
+        ui.table_headers_row();
+
+        for (name, value) in fields {
+            ui.table_next_row();
+            ui.table_set_column_index(0);
+            ui.text(name);
+            ui.table_next_column();
+            if slot.is_recently_changed(section, name, tick) {
+                ui.text_colored(ORANGE, value);
+            } else {
+                ui.text_wrapped(value);
+            }
+        }
+
+        table.end();
+    }
+}
+
+impl ObjectInspector {
+    pub fn draw(&mut self, ui: &Ui, session: &mut Session) {
+        if !session.inspecting {
+            self.slots.remove(&session.id);
+            return;
+        }
+
+        let index = session.target_index as usize;
+        let mut opened = true;
+        if let Some(inspector_window) = ui.window(format!("Object {} - {}###Inspector{}", index, session.name, session.id))
+            .opened(&mut opened)
+            .size([520.0, 600.0], Condition::FirstUseEver)
+            .position([300.0, 80.0], Condition::FirstUseEver)
+            .begin()
+        {
+            match &session.snapshot {
+                None => ui.text_colored(RED, "Nothing to show, there is no snapshot."),
+                Some(snapshot) if index >= snapshot.object_entries.len() => {
+                    ui.text_colored(RED, format!("Slot {} is outside the object table of {} slots", index, snapshot.object_entries.len()));
+                }
+                Some(snapshot) => {
+                    let header_entry = snapshot.object_header_entries[index].as_ref();
+                    let object = snapshot.object_entries[index].as_ref();
+                    let object_list_header = snapshot.object_list_headers[index].as_ref();
+                    let tick = snapshot.game_time_globals.local_time;
+
+                    // Start over when the slot was reused by another object, its fields have nothing to do with the old ones.
+                    let slot = self.slots.entry(session.id).or_default();
+                    let id = header_entry.map(|header_entry| header_entry.id);
+                    if slot.index != index || slot.id != id {
+                        *slot = InspectedSlot { index, id, ..InspectedSlot::default() };
+                    }
+
+                    match (header_entry, object) {
+                        (Some(header_entry), Some(object)) => {
+                            ui.text(format!("Datum: {}", Datum::from_parts(index as u16, header_entry.id).get_handle()));
+                            ui.text(format!("Type: {}", object_type_string(header_entry.data_type)));
+                            ui.text(format!("Tag: {}", snapshot.tags.get(&object.tag_index).map(String::as_str).unwrap_or("UNKNOWN")));
+                            ui.text(format!("Tick: {}", tick));
+
+                            let sections = [
+                                ("Object", fields(object)),
+                                ("Object Header Entry", fields(header_entry)),
+                                ("Object List Header", object_list_header.map(fields).unwrap_or_default())
+                            ];
+
+                            for (section, fields) in &sections {
+                                slot.track(section, fields, tick);
+                            }
+
+                            for (section, fields) in &sections {
+                                if ui.collapsing_header(section, TreeNodeFlags::DEFAULT_OPEN) {
+                                    draw_fields(ui, section, fields, slot, tick);
+                                }
+                            }
+                        }
+                        _ => ui.text_colored(RED, format!("Slot {} is free", index))
+                    }
+                }
+            }
+
+            inspector_window.end();
+        }
+
+        if !opened {
+            session.inspecting = false;
+        }
+    }
+}
@@ -0,0 +1,13 @@
+mod console;
+mod diff;
+mod events;
+mod inspector;
+mod path_dialog;
+mod server;
+
+pub use console::ScriptConsole;
+pub use diff::DiffWindow;
+pub use events::EventsWindow;
+pub use inspector::ObjectInspector;
+pub use path_dialog::PathDialog;
+pub use server::ServerWindow;
@@ -0,0 +1,88 @@
+use imgui::{Condition, Ui};
+
+use crate::{ORANGE, RED};
+
+// A small window asking for a path to act on, e.g. a dump to open or a file to export to.
+pub struct PathDialog {
+    pub open: bool,
+    pub path: String,
+    title: &'static str,
+    label: &'static str,
+    button: &'static str,
+    // How the last press of the button went, a status to keep showing or an error.
+    outcome: Option<Result<String, String>>
+}
+
+impl PathDialog {
+    pub fn new(title: &'static str, label: &'static str, button: &'static str) -> PathDialog {
+        PathDialog {
+            open: false,
+            path: String::default(),
+            title,
+            label,
+            button,
+            outcome: None
+        }
+    }
+
+    // Open the dialog afresh, with `path` filled in when given.
+    pub fn show(&mut self, path: Option<String>) {
+        if let Some(path) = path {
+            self.path = path;
+        }
+        self.outcome = None;
+        self.open = true;
+    }
+
+    // Draw the dialog with `options` between the path and the button, which are handed the path to adjust.
+    // Returns the path when the button was pressed, how acting on it went is reported back with finish.
+    pub fn draw(&mut self, ui: &Ui, options: impl FnOnce(&Ui, &mut String)) -> Option<String> {
+        if !self.open {
+            return None;
+        }
+
+        let mut pressed = false;
+        let mut opened = true;
+        if let Some(dialog_window) = ui.window(self.title)
+            .opened(&mut opened)
+            .size([600.0, 140.0], Condition::FirstUseEver)
+            .position([200.0, 100.0], Condition::FirstUseEver)
+            .collapsible(false)
+            .begin()
+        {
+            ui.input_text(self.label, &mut self.path)
+                .allow_tab_input(false)
+                .build();
+
+            options(ui, &mut self.path);
+
+            pressed = ui.button(self.button);
+
+            match &self.outcome {
+                Some(Ok(status)) => ui.text_colored(ORANGE, status),
+                Some(Err(error)) => ui.text_colored(RED, error),
+                None => {}
+            }
+
+            dialog_window.end();
+        }
+
+        if !opened {
+            self.open = false;
+        }
+
+        pressed.then(|| self.path.clone())
+    }
+
+    // Ok(None) is done and closes the dialog, a status or an error is shown in it.
+    pub fn finish(&mut self, result: Result<Option<String>, String>) {
+        match result {
+            Ok(None) => {
+                self.outcome = None;
+                self.open = false;
+            }
+            Ok(Some(status)) => self.outcome = Some(Ok(status)),
+            Err(error) => self.outcome = Some(Err(error))
+        }
+    }
+}
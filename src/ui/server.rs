@@ -0,0 +1,106 @@
+use std::io;
+
+use imgui::{Condition, Ui};
+
+use crate::{
+    server::{Server, DEFAULT_PORT},
+    session::Session,
+    ORANGE, RED
+};
+
+// Starts and stops the API server, which lives on outside the window.
+pub struct ServerWindow {
+    pub open: bool,
+    port: String,
+    error: Option<String>,
+    // Latest trouble the running server reported, e.g. a client that dropped.
+    server_error: Option<String>
+}
+
+impl Default for ServerWindow {
+    fn default() -> ServerWindow {
+        ServerWindow {
+            open: false,
+            port: DEFAULT_PORT.to_string(),
+            error: None,
+            server_error: None
+        }
+    }
+}
+
+impl ServerWindow {
+    pub fn show(&mut self) {
+        self.error = None;
+        self.open = true;
+    }
+
+    pub fn draw(&mut self, ui: &Ui, server: &mut Option<Server>, sessions: &[Session]) {
+        // Picked up while the window is closed as well, so they are there when it is opened.
+        if let Some(error) = server.as_ref().and_then(|server| server.take_errors().pop()) {
+            self.server_error = Some(error);
+        }
+
+        if !self.open {
+            return;
+        }
+
+        let mut opened = true;
+        if let Some(server_window) = ui.window("API Server")
+            .opened(&mut opened)
+            .size([600.0, 120.0], Condition::FirstUseEver)
+            .position([200.0, 100.0], Condition::FirstUseEver)
+            .collapsible(false)
+            .begin()
+        {
+            match server {
+                Some(running) => {
+                    ui.text(format!("Listening on ws://{} | Clients: {}", running.address(), running.client_count()));
+
+                    if ui.button("Stop") {
+                        *server = None;
+                        self.server_error = None;
+                    }
+                }
+                None => {
+                    ui.input_text("Port", &mut self.port)
+                        .chars_decimal(true)
+                        .allow_tab_input(false)
+                        .build();
+
+                    if ui.button("Start") {
+                        let started = self.port.parse()
+                            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid port"))
+                            .and_then(Server::start);
+
+                        match started {
+                            Ok(started) => {
+                                // Clients can query the sessions straight away, e.g. a paused replay.
+                                for session in sessions {
+                                    if let Some(snapshot) = &session.snapshot {
+                                        started.publish(session.id, &session.name, snapshot);
+                                    }
+                                }
+                                *server = Some(started);
+                                self.error = None;
+                            }
+                            Err(e) => self.error = Some(e.to_string())
+                        }
+                    }
+                }
+            }
+
+            if let Some(error) = &self.error {
+                ui.text_colored(RED, format!("Could not start the server: {}", error));
+            }
+            if let Some(server_error) = &self.server_error {
+                ui.text_colored(ORANGE, server_error);
+            }
+
+            server_window.end();
+        }
+
+        if !opened {
+            self.open = false;
+        }
+    }
+}